@@ -0,0 +1,379 @@
+use raylib::prelude::*;
+use raylib::core::input::key_from_i32;
+use std::fs;
+use std::path::Path;
+
+pub const GAMEPAD: i32 = 0;
+pub const AXIS_THRESHOLD: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    StrafeLeft,
+    StrafeRight,
+    TurnLeft,
+    TurnRight,
+    LookUp,
+    LookDown,
+    Sprint,
+    Crouch,
+    Use,
+    UseItem,
+    NextItem,
+    PrevItem,
+    Map,
+    Pause,
+    ToggleMouse,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    Confirm,
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 23] = [
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::LookUp,
+        Action::LookDown,
+        Action::Sprint,
+        Action::Crouch,
+        Action::Use,
+        Action::UseItem,
+        Action::NextItem,
+        Action::PrevItem,
+        Action::Map,
+        Action::Pause,
+        Action::ToggleMouse,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuLeft,
+        Action::MenuRight,
+        Action::Confirm,
+        Action::Back,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Action::MoveForward => "move_forward",
+            Action::MoveBack => "move_back",
+            Action::StrafeLeft => "strafe_left",
+            Action::StrafeRight => "strafe_right",
+            Action::TurnLeft => "turn_left",
+            Action::TurnRight => "turn_right",
+            Action::LookUp => "look_up",
+            Action::LookDown => "look_down",
+            Action::Sprint => "sprint",
+            Action::Crouch => "crouch",
+            Action::Use => "use",
+            Action::UseItem => "use_item",
+            Action::NextItem => "next_item",
+            Action::PrevItem => "prev_item",
+            Action::Map => "map",
+            Action::Pause => "pause",
+            Action::ToggleMouse => "toggle_mouse",
+            Action::MenuUp => "menu_up",
+            Action::MenuDown => "menu_down",
+            Action::MenuLeft => "menu_left",
+            Action::MenuRight => "menu_right",
+            Action::Confirm => "confirm",
+            Action::Back => "back",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::MoveForward => "Avanzar",
+            Action::MoveBack => "Retroceder",
+            Action::StrafeLeft => "Moverse a la izquierda",
+            Action::StrafeRight => "Moverse a la derecha",
+            Action::TurnLeft => "Girar a la izquierda",
+            Action::TurnRight => "Girar a la derecha",
+            Action::LookUp => "Mirar arriba",
+            Action::LookDown => "Mirar abajo",
+            Action::Sprint => "Correr",
+            Action::Crouch => "Agacharse",
+            Action::Use => "Interactuar",
+            Action::UseItem => "Usar objeto",
+            Action::NextItem => "Objeto siguiente",
+            Action::PrevItem => "Objeto anterior",
+            Action::Map => "Mostrar mapa",
+            Action::Pause => "Pausa",
+            Action::ToggleMouse => "Capturar raton",
+            Action::MenuUp => "Menu arriba",
+            Action::MenuDown => "Menu abajo",
+            Action::MenuLeft => "Menu izquierda",
+            Action::MenuRight => "Menu derecha",
+            Action::Confirm => "Aceptar",
+            Action::Back => "Volver",
+        }
+    }
+
+    pub fn rebindable(&self) -> bool {
+        !matches!(self, Action::MenuUp | Action::MenuDown | Action::MenuLeft | Action::MenuRight | Action::Confirm | Action::Back)
+    }
+
+    fn default_keys(&self) -> Vec<KeyboardKey> {
+        use KeyboardKey::*;
+        match self {
+            Action::MoveForward => vec![KEY_W, KEY_UP],
+            Action::MoveBack => vec![KEY_S, KEY_DOWN],
+            Action::StrafeLeft => vec![KEY_A],
+            Action::StrafeRight => vec![KEY_D],
+            Action::TurnLeft => vec![KEY_LEFT],
+            Action::TurnRight => vec![KEY_RIGHT],
+            Action::LookUp => vec![KEY_PAGE_UP],
+            Action::LookDown => vec![KEY_PAGE_DOWN],
+            Action::Sprint => vec![KEY_LEFT_SHIFT, KEY_RIGHT_SHIFT],
+            Action::Crouch => vec![KEY_C, KEY_LEFT_CONTROL],
+            Action::Use => vec![KEY_E],
+            Action::UseItem => vec![KEY_F],
+            Action::NextItem => vec![KEY_X],
+            Action::PrevItem => vec![KEY_Z],
+            Action::Map => vec![KEY_M],
+            Action::Pause => vec![KEY_P],
+            Action::ToggleMouse => vec![KEY_TAB],
+            Action::MenuUp => vec![KEY_UP],
+            Action::MenuDown => vec![KEY_DOWN],
+            Action::MenuLeft => vec![KEY_LEFT],
+            Action::MenuRight => vec![KEY_RIGHT],
+            Action::Confirm => vec![KEY_ENTER],
+            Action::Back => vec![KEY_BACKSPACE],
+        }
+    }
+
+    fn default_buttons(&self) -> Vec<GamepadButton> {
+        use GamepadButton::*;
+        match self {
+            Action::Sprint => vec![GAMEPAD_BUTTON_LEFT_THUMB],
+            Action::Crouch => vec![GAMEPAD_BUTTON_RIGHT_THUMB],
+            Action::Use => vec![GAMEPAD_BUTTON_RIGHT_FACE_DOWN],
+            Action::UseItem => vec![GAMEPAD_BUTTON_RIGHT_FACE_LEFT],
+            Action::NextItem => vec![GAMEPAD_BUTTON_RIGHT_TRIGGER_1],
+            Action::PrevItem => vec![GAMEPAD_BUTTON_LEFT_TRIGGER_1],
+            Action::Map => vec![GAMEPAD_BUTTON_MIDDLE_LEFT],
+            Action::Pause => vec![GAMEPAD_BUTTON_MIDDLE_RIGHT],
+            Action::MenuUp => vec![GAMEPAD_BUTTON_LEFT_FACE_UP],
+            Action::MenuDown => vec![GAMEPAD_BUTTON_LEFT_FACE_DOWN],
+            Action::MenuLeft => vec![GAMEPAD_BUTTON_LEFT_FACE_LEFT],
+            Action::MenuRight => vec![GAMEPAD_BUTTON_LEFT_FACE_RIGHT],
+            Action::Confirm => vec![GAMEPAD_BUTTON_RIGHT_FACE_DOWN],
+            Action::Back => vec![GAMEPAD_BUTTON_RIGHT_FACE_RIGHT],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn axis(&self) -> Option<(GamepadAxis, f32)> {
+        use GamepadAxis::*;
+        match self {
+            Action::MoveForward | Action::MenuUp => Some((GAMEPAD_AXIS_LEFT_Y, -1.0)),
+            Action::MoveBack | Action::MenuDown => Some((GAMEPAD_AXIS_LEFT_Y, 1.0)),
+            Action::StrafeLeft | Action::MenuLeft => Some((GAMEPAD_AXIS_LEFT_X, -1.0)),
+            Action::StrafeRight | Action::MenuRight => Some((GAMEPAD_AXIS_LEFT_X, 1.0)),
+            Action::TurnLeft => Some((GAMEPAD_AXIS_RIGHT_X, -1.0)),
+            Action::TurnRight => Some((GAMEPAD_AXIS_RIGHT_X, 1.0)),
+            Action::LookUp => Some((GAMEPAD_AXIS_RIGHT_Y, -1.0)),
+            Action::LookDown => Some((GAMEPAD_AXIS_RIGHT_Y, 1.0)),
+            Action::Sprint => Some((GAMEPAD_AXIS_RIGHT_TRIGGER, 1.0)),
+            _ => None,
+        }
+    }
+}
+
+pub fn button_name(button: GamepadButton) -> &'static str {
+    use GamepadButton::*;
+    match button {
+        GAMEPAD_BUTTON_LEFT_FACE_UP => "Cruceta arriba",
+        GAMEPAD_BUTTON_LEFT_FACE_DOWN => "Cruceta abajo",
+        GAMEPAD_BUTTON_LEFT_FACE_LEFT => "Cruceta izq.",
+        GAMEPAD_BUTTON_LEFT_FACE_RIGHT => "Cruceta der.",
+        GAMEPAD_BUTTON_RIGHT_FACE_DOWN => "A",
+        GAMEPAD_BUTTON_RIGHT_FACE_RIGHT => "B",
+        GAMEPAD_BUTTON_RIGHT_FACE_LEFT => "X",
+        GAMEPAD_BUTTON_RIGHT_FACE_UP => "Y",
+        GAMEPAD_BUTTON_LEFT_TRIGGER_1 => "LB",
+        GAMEPAD_BUTTON_RIGHT_TRIGGER_1 => "RB",
+        GAMEPAD_BUTTON_LEFT_TRIGGER_2 => "LT",
+        GAMEPAD_BUTTON_RIGHT_TRIGGER_2 => "RT",
+        GAMEPAD_BUTTON_MIDDLE_LEFT => "Select",
+        GAMEPAD_BUTTON_MIDDLE_RIGHT => "Start",
+        GAMEPAD_BUTTON_LEFT_THUMB => "L3",
+        GAMEPAD_BUTTON_RIGHT_THUMB => "R3",
+        _ => "?",
+    }
+}
+
+fn axis_name(axis: GamepadAxis, sign: f32) -> &'static str {
+    use GamepadAxis::*;
+    match (axis, sign > 0.0) {
+        (GAMEPAD_AXIS_LEFT_Y, false) => "Stick izq. arriba",
+        (GAMEPAD_AXIS_LEFT_Y, true) => "Stick izq. abajo",
+        (GAMEPAD_AXIS_LEFT_X, false) => "Stick izq. izquierda",
+        (GAMEPAD_AXIS_LEFT_X, true) => "Stick izq. derecha",
+        (GAMEPAD_AXIS_RIGHT_X, false) => "Stick der. izquierda",
+        (GAMEPAD_AXIS_RIGHT_X, true) => "Stick der. derecha",
+        (GAMEPAD_AXIS_RIGHT_Y, false) => "Stick der. arriba",
+        (GAMEPAD_AXIS_RIGHT_Y, true) => "Stick der. abajo",
+        (GAMEPAD_AXIS_RIGHT_TRIGGER, _) => "RT",
+        (GAMEPAD_AXIS_LEFT_TRIGGER, _) => "LT",
+    }
+}
+
+pub fn key_name(key: KeyboardKey) -> String {
+    let name = format!("{:?}", key);
+    name.strip_prefix("KEY_").unwrap_or(&name).to_string()
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyboardKey> {
+    let wanted = name.trim().to_uppercase();
+    (0..400).filter_map(key_from_i32).find(|k| key_name(*k) == wanted)
+}
+
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyboardKey>)>,
+    buttons: Vec<(Action, Vec<GamepadButton>)>,
+}
+
+impl Keymap {
+    pub fn defaults() -> Self {
+        Keymap {
+            bindings: Action::ALL.iter().map(|a| (*a, a.default_keys())).collect(),
+            buttons: Action::ALL.iter().map(|a| (*a, a.default_buttons())).collect(),
+        }
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut map = Keymap::defaults();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let Some((id, keys)) = line.split_once('=') else {
+                eprintln!("Keymap: linea {} invalida", n + 1);
+                continue;
+            };
+            let Some(action) = Action::ALL.iter().find(|a| a.id() == id.trim()) else {
+                eprintln!("Keymap: accion desconocida '{}' en la linea {}", id.trim(), n + 1);
+                continue;
+            };
+            let mut parsed = Vec::new();
+            for k in keys.split(',').filter(|k| !k.trim().is_empty()) {
+                match key_from_name(k) {
+                    Some(key) => parsed.push(key),
+                    None => eprintln!("Keymap: tecla desconocida '{}' en la linea {}", k.trim(), n + 1),
+                }
+            }
+            if !parsed.is_empty() { map.set_keys(*action, parsed); }
+        }
+        map
+    }
+
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(text) => Keymap::parse(&text),
+            Err(_) => Keymap::defaults(),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for (action, keys) in self.bindings.iter() {
+            let names: Vec<String> = keys.iter().map(|k| key_name(*k)).collect();
+            out.push_str(&format!("{} = {}\n", action.id(), names.join(", ")));
+        }
+        out
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+        fs::write(path, self.to_text())
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyboardKey] {
+        self.bindings.iter().find(|(a, _)| *a == action).map(|(_, k)| k.as_slice()).unwrap_or(&[])
+    }
+
+    fn set_keys(&mut self, action: Action, keys: Vec<KeyboardKey>) {
+        if let Some(entry) = self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            entry.1 = keys;
+        }
+    }
+
+    pub fn rebind(&mut self, action: Action, key: KeyboardKey) {
+        for (a, keys) in self.bindings.iter_mut() {
+            if *a != action && a.rebindable() { keys.retain(|k| *k != key); }
+        }
+        if let Some((_, keys)) = self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            keys.retain(|k| *k != key);
+            if keys.is_empty() { keys.push(key); } else { keys[0] = key; }
+        }
+    }
+
+    pub fn describe(&self, action: Action) -> String {
+        let keys = self.keys(action);
+        if keys.is_empty() { return "-".to_string(); }
+        keys.iter().map(|k| key_name(*k)).collect::<Vec<_>>().join(" / ")
+    }
+
+    pub fn buttons(&self, action: Action) -> &[GamepadButton] {
+        self.buttons.iter().find(|(a, _)| *a == action).map(|(_, b)| b.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn describe_pad(&self, action: Action) -> String {
+        let mut names: Vec<&str> = self.buttons(action).iter().map(|b| button_name(*b)).collect();
+        if let Some((axis, sign)) = action.axis() { names.push(axis_name(axis, sign)); }
+        if names.is_empty() { return "-".to_string(); }
+        names.join(" / ")
+    }
+
+    pub fn down(&self, window: &RaylibHandle, action: Action) -> bool {
+        self.keys(action).iter().any(|k| window.is_key_down(*k))
+            || (window.is_gamepad_available(GAMEPAD) && self.buttons(action).iter().any(|b| window.is_gamepad_button_down(GAMEPAD, *b)))
+    }
+
+    pub fn pressed(&self, window: &RaylibHandle, action: Action) -> bool {
+        self.keys(action).iter().any(|k| window.is_key_pressed(*k))
+            || (window.is_gamepad_available(GAMEPAD) && self.buttons(action).iter().any(|b| window.is_gamepad_button_pressed(GAMEPAD, *b)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use KeyboardKey::*;
+
+    #[test]
+    fn text_round_trip() {
+        let mut map = Keymap::defaults();
+        map.rebind(Action::Use, KEY_G);
+        map.rebind(Action::Crouch, KEY_V);
+        let loaded = Keymap::parse(&map.to_text());
+        for action in Action::ALL.iter() {
+            assert_eq!(loaded.keys(*action), map.keys(*action), "{}", action.id());
+        }
+    }
+
+    #[test]
+    fn unknown_actions_and_keys_are_ignored() {
+        let map = Keymap::parse("volar = Q\nuse = NOEXISTE, G\nmap = NOEXISTE\nbasura\n");
+        assert_eq!(map.keys(Action::Use), &[KEY_G]);
+        assert_eq!(map.keys(Action::Map), Keymap::defaults().keys(Action::Map));
+    }
+
+    #[test]
+    fn rebind_steals_the_key_from_gameplay_actions_only() {
+        let mut map = Keymap::defaults();
+        map.rebind(Action::Use, KEY_W);
+        map.rebind(Action::Use, KEY_UP);
+        assert_eq!(map.keys(Action::Use)[0], KEY_UP);
+        assert!(!map.keys(Action::MoveForward).contains(&KEY_W));
+        assert!(!map.keys(Action::MoveForward).contains(&KEY_UP));
+        assert!(map.keys(Action::MenuUp).contains(&KEY_UP));
+    }
+}
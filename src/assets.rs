@@ -0,0 +1,259 @@
+use std::fs;
+use std::io::Cursor;
+use std::sync::Arc;
+use image::{Rgba, RgbaImage};
+use rodio::Decoder;
+
+pub const MANIFEST_PATH: &str = "assets/manifest.cfg";
+const EMBEDDED_MANIFEST: &str = include_str!("../assets/manifest.cfg");
+const PLACEHOLDER_SIZE: u32 = 64;
+const PLACEHOLDER_CHECK: u32 = 8;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AssetKind {
+    Wall,
+    Sprite,
+    Screen,
+    Sound,
+    Music,
+}
+
+impl AssetKind {
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "wall" => Some(AssetKind::Wall),
+            "sprite" => Some(AssetKind::Sprite),
+            "screen" => Some(AssetKind::Screen),
+            "sound" => Some(AssetKind::Sound),
+            "music" => Some(AssetKind::Music),
+            _ => None,
+        }
+    }
+
+    fn is_image(self) -> bool {
+        matches!(self, AssetKind::Wall | AssetKind::Sprite | AssetKind::Screen)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssetEntry {
+    pub kind: AssetKind,
+    pub id: String,
+    pub path: String,
+    pub rect: Option<[u32; 4]>,
+    pub optional: bool,
+}
+
+impl AssetEntry {
+    pub fn extension(&self) -> String {
+        match self.path.rsplit_once('.') {
+            Some((_, ext)) => format!(".{}", ext.to_lowercase()),
+            None => String::new(),
+        }
+    }
+}
+
+pub fn parse_manifest(text: &str) -> Vec<AssetEntry> {
+    let mut entries = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let kind = parts.first().and_then(|k| AssetKind::parse(k));
+        let optional = parts.last() == Some(&"opcional");
+        let rect = match parts.get(3..parts.len() - optional as usize) {
+            Some([]) => Some(None),
+            Some([r]) => parse_rect(r).map(Some),
+            _ => None,
+        };
+        match (kind, rect) {
+            (Some(kind), Some(rect)) if rect.is_none() || kind.is_image() => entries.push(AssetEntry {
+                kind,
+                id: parts[1].to_string(),
+                path: parts[2].to_string(),
+                rect,
+                optional,
+            }),
+            _ => eprintln!("Manifiesto: linea {} invalida", n + 1),
+        }
+    }
+    entries
+}
+
+fn parse_rect(text: &str) -> Option<[u32; 4]> {
+    let values: Vec<u32> = text.split(',').map(|v| v.trim().parse().ok()).collect::<Option<_>>()?;
+    match values[..] {
+        [x, y, w, h] if w > 0 && h > 0 => Some([x, y, w, h]),
+        _ => None,
+    }
+}
+
+pub fn load_manifest() -> Vec<AssetEntry> {
+    match fs::read_to_string(MANIFEST_PATH) {
+        Ok(text) => parse_manifest(&text),
+        Err(e) => {
+            eprintln!("Manifiesto: no se pudo leer '{}' ({}), se usa el integrado", MANIFEST_PATH, e);
+            parse_manifest(EMBEDDED_MANIFEST)
+        }
+    }
+}
+
+pub fn placeholder_image() -> RgbaImage {
+    RgbaImage::from_fn(PLACEHOLDER_SIZE, PLACEHOLDER_SIZE, |x, y| {
+        if (x / PLACEHOLDER_CHECK + y / PLACEHOLDER_CHECK).is_multiple_of(2) { Rgba([255, 0, 255, 255]) } else { Rgba([0, 0, 0, 255]) }
+    })
+}
+
+pub struct Asset {
+    pub entry: AssetEntry,
+    pub bytes: Arc<[u8]>,
+    pub image: Option<RgbaImage>,
+}
+
+fn read_asset(entry: &AssetEntry) -> Result<Asset, String> {
+    let bytes: Arc<[u8]> = fs::read(&entry.path).map_err(|e| format!("{}: {}", entry.path, e))?.into();
+    let image = if entry.kind.is_image() {
+        let image = image::load_from_memory(&bytes).map_err(|e| format!("{}: {}", entry.path, e))?.to_rgba8();
+        if let Some([x, y, w, h]) = entry.rect && (x + w > image.width() || y + h > image.height()) {
+            return Err(format!("{}: la region {},{},{},{} se sale de la imagen", entry.path, x, y, w, h));
+        }
+        Some(image)
+    } else {
+        Decoder::new(Cursor::new(Arc::clone(&bytes))).map_err(|e| format!("{}: {}", entry.path, e))?;
+        None
+    };
+    Ok(Asset { entry: entry.clone(), bytes, image })
+}
+
+#[derive(Default)]
+pub struct Assets {
+    entries: Vec<AssetEntry>,
+    items: Vec<Asset>,
+    pub missing: Vec<String>,
+}
+
+impl Assets {
+    pub fn entries(&self) -> &[AssetEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, kind: AssetKind, id: &str) -> Option<&Asset> {
+        self.items.iter().find(|a| a.entry.kind == kind && a.entry.id == id)
+    }
+
+    pub fn of_kind(&self, kind: AssetKind) -> impl Iterator<Item = &Asset> {
+        self.items.iter().filter(move |a| a.entry.kind == kind)
+    }
+
+    pub fn ids(&self, kind: AssetKind) -> impl Iterator<Item = &str> {
+        self.entries.iter().filter(move |e| e.kind == kind).map(|e| e.id.as_str())
+    }
+
+    pub fn image(&self, kind: AssetKind, id: &str) -> RgbaImage {
+        self.get(kind, id).and_then(|a| a.image.clone()).unwrap_or_else(placeholder_image)
+    }
+
+    pub fn rect(&self, kind: AssetKind, id: &str) -> Option<[u32; 4]> {
+        self.get(kind, id).and_then(|a| a.entry.rect)
+    }
+
+    pub fn reload(&mut self, entry: &AssetEntry) -> Result<&Asset, String> {
+        let asset = read_asset(entry)?;
+        self.missing.retain(|m| !m.starts_with(&entry.path));
+        let i = match self.items.iter().position(|a| a.entry.kind == entry.kind && a.entry.id == entry.id) {
+            Some(i) => { self.items[i] = asset; i }
+            None => { self.items.push(asset); self.items.len() - 1 }
+        };
+        Ok(&self.items[i])
+    }
+
+    pub fn report(&self) -> Option<String> {
+        if self.missing.is_empty() { return None; }
+        Some(format!("Faltan {} recursos: {}", self.missing.len(), self.missing.join(", ")))
+    }
+}
+
+pub struct AssetLoader {
+    next: usize,
+    assets: Assets,
+}
+
+impl AssetLoader {
+    pub fn new(entries: Vec<AssetEntry>) -> Self {
+        AssetLoader { next: 0, assets: Assets { entries, ..Default::default() } }
+    }
+
+    pub fn done(&self) -> bool {
+        self.next >= self.assets.entries.len()
+    }
+
+    pub fn progress(&self) -> f32 {
+        let total = self.assets.entries.len();
+        if total == 0 { 1.0 } else { self.next as f32 / total as f32 }
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.assets.entries.get(self.next).map(|e| e.path.as_str())
+    }
+
+    pub fn load_next(&mut self) {
+        let Some(entry) = self.assets.entries.get(self.next) else { return; };
+        self.next += 1;
+        let shared = self.assets.items.iter().find(|a| a.entry.path == entry.path && a.entry.kind.is_image() == entry.kind.is_image());
+        let loaded = match shared {
+            Some(a) => Ok(Asset { entry: entry.clone(), bytes: Arc::clone(&a.bytes), image: a.image.clone() }),
+            None => read_asset(entry),
+        };
+        match loaded {
+            Ok(asset) => self.assets.items.push(asset),
+            Err(e) if !entry.optional => self.assets.missing.push(e),
+            Err(_) => {}
+        }
+    }
+
+    pub fn finish(mut self) -> Assets {
+        while !self.done() { self.load_next(); }
+        self.assets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_parses_kinds_and_optional_flag() {
+        let entries = parse_manifest("# comentario\nwall + assets/a.png\nscreen win assets/win.png opcional\nsonido x y\nsprite k assets/k.png extra\n");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], AssetEntry { kind: AssetKind::Wall, id: "+".into(), path: "assets/a.png".into(), rect: None, optional: false });
+        assert!(entries[1].optional && entries[1].kind == AssetKind::Screen);
+        assert_eq!(entries[1].extension(), ".png");
+    }
+
+    #[test]
+    fn manifest_parses_atlas_regions() {
+        let entries = parse_manifest("wall t assets/atlas.png 64,0,64,64\nwall b assets/atlas.png 0,0,64 opcional\nsound s assets/s.ogg 0,0,1,1\nwall v assets/atlas.png 0,64,64,64 opcional\n");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].rect, Some([64, 0, 64, 64]));
+        assert!(entries[1].optional && entries[1].rect == Some([0, 64, 64, 64]));
+    }
+
+    #[test]
+    fn missing_required_files_are_all_reported() {
+        let entries = parse_manifest("sprite a no_existe/a.png\nsound b no_existe/b.ogg\nmusic c no_existe/c.ogg opcional\n");
+        let mut loader = AssetLoader::new(entries);
+        loader.load_next();
+        assert_eq!(loader.progress(), 1.0 / 3.0);
+        let assets = loader.finish();
+        assert_eq!(assets.missing.len(), 2);
+        assert!(assets.report().unwrap().contains("no_existe/b.ogg"));
+        assert_eq!(assets.image(AssetKind::Sprite, "a"), placeholder_image());
+    }
+
+    #[test]
+    fn embedded_manifest_is_valid() {
+        let entries = parse_manifest(EMBEDDED_MANIFEST);
+        assert!(entries.iter().any(|e| e.kind == AssetKind::Wall && e.id == "D"));
+        assert!(entries.iter().all(|e| !e.path.is_empty()));
+    }
+}
@@ -1,97 +1,305 @@
-use std::fs::File;
-use std::io::BufReader;
+use crate::assets::{Asset, AssetKind, Assets};
+use crate::audio_backend::{AudioBackend, NullBackend, RodioBackend, VoiceId};
+use crate::events::{GameEvent, Subscriber};
+use crate::spatial::SpatialParams;
+use crate::music::{MusicController, MusicState};
 
-use rodio::{Decoder, OutputStream, OutputStreamBuilder, Sink};
-use std::sync::Mutex;
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bus {
+    Master,
+    Music,
+    Sfx,
+    Ambient,
+}
+
+impl Bus {
+    pub const ALL: [Bus; 4] = [Bus::Master, Bus::Music, Bus::Sfx, Bus::Ambient];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Bus::Master => "Volumen general",
+            Bus::Music => "Volumen musica",
+            Bus::Sfx => "Volumen efectos",
+            Bus::Ambient => "Volumen ambiente",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+const MAX_VOICES: usize = 8;
+const SFX_DUCK: f32 = 0.4;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SoundId {
+    Step,
+    Hurt,
+    Victory,
+    GameOver,
+    EnemyHum,
+    Tension,
+}
+
+impl SoundId {
+    pub const ALL: [SoundId; 6] = [SoundId::Step, SoundId::Hurt, SoundId::Victory, SoundId::GameOver, SoundId::EnemyHum, SoundId::Tension];
+
+    pub fn asset_id(self) -> Option<&'static str> {
+        match self {
+            SoundId::Step => Some("step"),
+            SoundId::Hurt => Some("hurt"),
+            SoundId::Victory => Some("victory"),
+            SoundId::GameOver => Some("gameover"),
+            SoundId::EnemyHum | SoundId::Tension => None,
+        }
+    }
+
+    fn volume(self) -> f32 {
+        match self {
+            SoundId::Step => 0.75,
+            _ => 0.3,
+        }
+    }
+
+    fn priority(self) -> u8 {
+        match self {
+            SoundId::Step | SoundId::EnemyHum | SoundId::Tension => 0,
+            SoundId::Hurt => 1,
+            SoundId::Victory | SoundId::GameOver => 2,
+        }
+    }
+}
+
+struct Emitter {
+    key: usize,
+    voice: VoiceId,
+}
+
+struct Voice {
+    id: VoiceId,
+    bus: Bus,
+    volume: f32,
+    priority: u8,
+}
 
 pub struct AudioManager {
-    stream: OutputStream,   
-    music_sink: Sink,      
-    sfx_master_volume: f32,  
-    sfx_sinks: Mutex<Vec<Sink>>,
+    backend: Box<dyn AudioBackend>,
+    music: MusicController,
+    duck: f32,
+    duck_target: f32,
+    sfx_duck: f32,
+    bus_volumes: [f32; 4],
+    muted: bool,
+    voices: Vec<Voice>,
+    emitters: Vec<Emitter>,
 }
 
 impl AudioManager {
-    pub fn new_loop(music_path: &str, music_volume: f32, sfx_volume: f32) -> Self {
-        let stream = OutputStreamBuilder::open_default_stream()
-            .expect("AudioManager: no se pudo inicializar el stream de audio por defecto");
-
-        let music_sink = Sink::connect_new(&stream.mixer());
-
-        match File::open(music_path) {
-            Ok(file) => {
-                let buf = BufReader::new(file);
-                match Decoder::new_looped(buf) {
-                    Ok(decoder) => {
-                        music_sink.append(decoder);
-                    }
-                    Err(e) => {
-                        eprintln!("AudioManager: error decodificando '{}': {}", music_path, e);
-                    }
-                }
-            }
+    pub fn new(assets: &Assets) -> Self {
+        match RodioBackend::open(assets) {
+            Ok(backend) => AudioManager::with_backend(Box::new(backend)),
             Err(e) => {
-                eprintln!("AudioManager: no se pudo abrir '{}': {}", music_path, e);
+                eprintln!("AudioManager: sin dispositivo de audio ({}), se continua en silencio", e);
+                AudioManager::with_backend(Box::new(NullBackend))
             }
         }
+    }
 
-        music_sink.set_volume(music_volume.clamp(0.0, 1.0));
-        music_sink.play();
-
+    pub fn with_backend(mut backend: Box<dyn AudioBackend>) -> Self {
+        let music = MusicController::new(backend.as_mut());
         AudioManager {
-            stream,
-            music_sink,
-            sfx_master_volume: sfx_volume.clamp(0.0, 1.0),
-            sfx_sinks: Mutex::new(Vec::new()),
+            backend,
+            music,
+            duck: 1.0,
+            duck_target: 1.0,
+            sfx_duck: 0.0,
+            bus_volumes: [1.0; 4],
+            muted: false,
+            voices: Vec::new(),
+            emitters: Vec::new(),
         }
     }
 
-    pub fn set_music_volume(&mut self, volume: f32) {
-        self.music_sink.set_volume(volume.clamp(0.0, 1.0));
+    pub fn gain(&self, bus: Bus) -> f32 {
+        if self.muted { return 0.0; }
+        let master = self.bus_volumes[Bus::Master.index()];
+        if bus == Bus::Master { master } else { master * self.bus_volumes[bus.index()] }
     }
 
-    pub fn set_sfx_volume(&mut self, volume: f32) {
-        self.sfx_master_volume = volume.clamp(0.0, 1.0);
+    pub fn set_bus_volume(&mut self, bus: Bus, volume: f32) {
+        self.bus_volumes[bus.index()] = volume.clamp(0.0, 1.0);
+        self.apply_volumes();
     }
 
-    pub fn play_sfx(&self, path: &str, vol: f32) {
-        match File::open(path) {
-            Ok(file) => {
-                let buf = BufReader::new(file);
-                match Decoder::new(buf) {
-                    Ok(decoder) => {
-                        let sink = Sink::connect_new(&self.stream.mixer());
-                        let final_vol = (vol.clamp(0.0, 1.0) * self.sfx_master_volume).clamp(0.0, 1.0);
-                        sink.append(decoder);
-                        sink.set_volume(final_vol);
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.apply_volumes();
+    }
 
-                        eprintln!("AudioManager: playing SFX '{}' vol={} final_vol={}", path, vol, final_vol);
+    fn apply_volumes(&mut self) {
+        let volume = self.gain(Bus::Music) * self.duck;
+        self.music.apply(self.backend.as_mut(), volume);
+        for v in self.voices.iter() {
+            let gain = self.gain(v.bus);
+            self.backend.set_volume(v.id, v.volume * gain);
+        }
+        let ambient = self.gain(Bus::Ambient);
+        for e in self.emitters.iter() {
+            self.backend.set_volume(e.voice, ambient);
+        }
+    }
 
-                        if let Ok(mut vec) = self.sfx_sinks.lock() {
-                            vec.push(sink);
-                            vec.retain(|s| !s.empty());
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("AudioManager: error decodificando SFX '{}': {}", path, e);
-                    }
+    pub fn update_emitters(&mut self, sources: &[(usize, SpatialParams)]) {
+        let backend = self.backend.as_mut();
+        self.emitters.retain(|e| {
+            let keep = sources.iter().any(|(k, _)| *k == e.key);
+            if !keep { backend.stop(e.voice); }
+            keep
+        });
+        for (key, params) in sources.iter() {
+            let voice = match self.emitters.iter().find(|e| e.key == *key) {
+                Some(e) => e.voice,
+                None => {
+                    let Some(voice) = self.backend.play_loop(SoundId::EnemyHum, self.gain(Bus::Ambient)) else { continue; };
+                    self.emitters.push(Emitter { key: *key, voice });
+                    voice
                 }
-            }
-            Err(e) => {
-                eprintln!("AudioManager: no se pudo abrir SFX '{}' : {}", path, e);
-            }
+            };
+            let (left, right) = params.ear_gains();
+            self.backend.set_spatial(voice, left, right, params.muffled);
         }
     }
 
-    pub fn pause_music(&self) {
-        self.music_sink.pause();
+    pub fn duck_music(&mut self, level: f32) {
+        self.duck_target = level.clamp(0.0, 1.0);
     }
 
-    pub fn resume_music(&self) {
-        self.music_sink.play();
+    pub fn update(&mut self, dt: f32) {
+        const DUCK_SPEED: f32 = 2.5;
+        let backend = self.backend.as_mut();
+        self.voices.retain(|v| {
+            let alive = backend.is_playing(v.id);
+            if !alive { backend.stop(v.id); }
+            alive
+        });
+        self.sfx_duck = (self.sfx_duck - dt).max(0.0);
+        let target = if self.sfx_duck > 0.0 { self.duck_target.min(SFX_DUCK) } else { self.duck_target };
+        let step = DUCK_SPEED * dt;
+        self.duck = if self.duck < target {
+            (self.duck + step).min(target)
+        } else {
+            (self.duck - step).max(target)
+        };
+        let volume = self.gain(Bus::Music) * self.duck;
+        self.music.update(self.backend.as_mut(), dt, volume);
     }
 
-    pub fn stop_music(&self) {
-        self.music_sink.stop();
+    pub fn reload_asset(&mut self, asset: &Asset) {
+        self.backend.load_asset(asset);
+        if asset.entry.kind == AssetKind::Music {
+            self.music.restart(self.backend.as_mut());
+            self.apply_volumes();
+        }
+    }
+
+    pub fn set_music(&mut self, state: MusicState, tension: f32) {
+        self.music.set_state(state);
+        self.music.set_tension(tension);
+    }
+
+    pub fn play(&mut self, id: SoundId) {
+        if self.voices.len() >= MAX_VOICES {
+            let backend = self.backend.as_ref();
+            self.voices.retain(|v| backend.is_playing(v.id));
+        }
+        if self.voices.len() >= MAX_VOICES {
+            let victim = self
+                .voices
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| v.priority <= id.priority())
+                .min_by_key(|(_, v)| v.priority)
+                .map(|(i, _)| i);
+            let Some(i) = victim else { return; };
+            let stolen = self.voices.remove(i);
+            self.backend.stop(stolen.id);
+        }
+
+        if id.priority() > 0 {
+            self.sfx_duck = self.sfx_duck.max(self.backend.clip_secs(id));
+        }
+
+        if let Some(voice) = self.backend.play(id, id.volume() * self.gain(Bus::Sfx)) {
+            self.voices.push(Voice { id: voice, bus: Bus::Sfx, volume: id.volume(), priority: id.priority() });
+        }
+    }
+}
+
+pub struct SfxSubscriber<'a>(pub &'a mut AudioManager);
+
+impl Subscriber for SfxSubscriber<'_> {
+    fn on_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Footstep => self.0.play(SoundId::Step),
+            GameEvent::Hurt(_) => self.0.play(SoundId::Hurt),
+            GameEvent::Victory => self.0.play(SoundId::Victory),
+            GameEvent::Death => self.0.play(SoundId::GameOver),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_backend::{AudioCall, RecordingBackend};
+
+    fn manager() -> (AudioManager, std::rc::Rc<std::cell::RefCell<Vec<AudioCall>>>) {
+        let backend = RecordingBackend::default();
+        let calls = backend.calls.clone();
+        let audio = AudioManager::with_backend(Box::new(backend));
+        calls.borrow_mut().clear();
+        (audio, calls)
+    }
+
+    fn played(calls: &[AudioCall]) -> Vec<(SoundId, f32)> {
+        calls.iter().filter_map(|c| match c { AudioCall::Play(s, v) => Some((*s, *v)), _ => None }).collect()
+    }
+
+    #[test]
+    fn events_trigger_sounds_scaled_by_bus_volume() {
+        let (mut audio, calls) = manager();
+        audio.set_bus_volume(Bus::Sfx, 0.5);
+        for event in [GameEvent::Footstep, GameEvent::PickedUp(crate::inventory::Item::Medkit), GameEvent::Hurt(50.0)] {
+            SfxSubscriber(&mut audio).on_event(&event);
+        }
+        assert_eq!(played(&calls.borrow()), vec![(SoundId::Step, 0.375), (SoundId::Hurt, 0.15)]);
+    }
+
+    #[test]
+    fn mute_silences_new_and_playing_voices() {
+        let (mut audio, calls) = manager();
+        audio.play(SoundId::Step);
+        let step = audio.voices[0].id;
+        audio.set_muted(true);
+        audio.play(SoundId::Hurt);
+        let calls = calls.borrow();
+        assert!(calls.contains(&AudioCall::Volume(step, 0.0)));
+        assert_eq!(played(&calls).last(), Some(&(SoundId::Hurt, 0.0)));
+    }
+
+    #[test]
+    fn full_voice_pool_steals_lowest_priority() {
+        let (mut audio, calls) = manager();
+        for _ in 0..MAX_VOICES { audio.play(SoundId::Step); }
+        audio.play(SoundId::GameOver);
+        let stops = calls.borrow().iter().filter(|c| matches!(c, AudioCall::Stop(_))).count();
+        assert_eq!(stops, 1);
+        assert_eq!(audio.voices.len(), MAX_VOICES);
+
+        for _ in 0..MAX_VOICES { audio.play(SoundId::Victory); }
+        audio.play(SoundId::Step);
+        assert_eq!(played(&calls.borrow()).last(), Some(&(SoundId::Victory, 0.3)));
     }
 }
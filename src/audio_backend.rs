@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rodio::buffer::SamplesBuffer;
+use rodio::source::ChannelVolume;
+use rodio::{Decoder, OutputStream, OutputStreamBuilder, Sink, Source};
+
+use crate::assets::{Asset, AssetKind, Assets};
+use crate::audio::SoundId;
+
+pub type VoiceId = u32;
+
+pub trait AudioBackend {
+    fn play(&mut self, sound: SoundId, volume: f32) -> Option<VoiceId>;
+    fn play_loop(&mut self, sound: SoundId, volume: f32) -> Option<VoiceId>;
+    fn has_music(&self, id: &str) -> bool;
+    fn play_music(&mut self, id: &str, volume: f32) -> Option<VoiceId>;
+    fn set_volume(&mut self, voice: VoiceId, volume: f32);
+    fn set_spatial(&mut self, voice: VoiceId, left: f32, right: f32, muffled: bool);
+    fn stop(&mut self, voice: VoiceId);
+    fn is_playing(&self, voice: VoiceId) -> bool;
+    fn clip_secs(&self, sound: SoundId) -> f32;
+    fn load_asset(&mut self, asset: &Asset);
+}
+
+fn decode_clip(bytes: &Arc<[u8]>) -> Result<SamplesBuffer, String> {
+    let decoder = Decoder::new(Cursor::new(Arc::clone(bytes))).map_err(|e| e.to_string())?;
+    let (channels, rate) = (decoder.channels(), decoder.sample_rate());
+    let samples: Vec<f32> = decoder.collect();
+    Ok(SamplesBuffer::new(channels, rate, samples))
+}
+
+fn synth_hum() -> SamplesBuffer {
+    const RATE: u32 = 22050;
+    let samples: Vec<f32> = (0..RATE)
+        .map(|i| {
+            let t = i as f32 / RATE as f32;
+            let wobble = 0.6 + 0.4 * (2.0 * PI * 3.0 * t).sin();
+            let tone = (2.0 * PI * 110.0 * t).sin() * 0.6 + (2.0 * PI * 165.0 * t).sin() * 0.3;
+            tone * wobble * 0.5
+        })
+        .collect();
+    SamplesBuffer::new(1, RATE, samples)
+}
+
+fn synth_placeholder() -> SamplesBuffer {
+    const RATE: u32 = 22050;
+    let samples: Vec<f32> = (0..RATE / 5).map(|i| (2.0 * PI * 880.0 * i as f32 / RATE as f32).sin().signum() * 0.3).collect();
+    SamplesBuffer::new(1, RATE, samples)
+}
+
+fn synth_pulse() -> SamplesBuffer {
+    const RATE: u32 = 22050;
+    let samples: Vec<f32> = (0..RATE * 2)
+        .map(|i| {
+            let t = i as f32 / RATE as f32;
+            let beat = (t * 2.0).fract();
+            let env = (1.0 - beat * 4.0).max(0.0);
+            let drone = (2.0 * PI * 55.0 * t).sin() * 0.4 + (2.0 * PI * 58.0 * t).sin() * 0.2;
+            let thump = (2.0 * PI * 70.0 * t).sin() * env * 0.6;
+            (drone + thump) * 0.5
+        })
+        .collect();
+    SamplesBuffer::new(1, RATE, samples)
+}
+
+const OPEN_CUTOFF: u32 = 18000;
+const MUFFLED_CUTOFF: u32 = 900;
+
+struct EarMix {
+    left: f32,
+    right: f32,
+    cutoff: u32,
+}
+
+struct RodioVoice {
+    sink: Sink,
+    mix: Option<Arc<Mutex<EarMix>>>,
+}
+
+pub struct RodioBackend {
+    stream: OutputStream,
+    bank: Vec<(SoundId, SamplesBuffer)>,
+    music: HashMap<String, Arc<[u8]>>,
+    voices: HashMap<VoiceId, RodioVoice>,
+    next_id: VoiceId,
+}
+
+impl RodioBackend {
+    pub fn open(assets: &Assets) -> Result<Self, String> {
+        let stream = OutputStreamBuilder::open_default_stream().map_err(|e| e.to_string())?;
+        let mut bank = Vec::new();
+        for id in SoundId::ALL {
+            let clip = match id {
+                SoundId::EnemyHum => synth_hum(),
+                SoundId::Tension => synth_pulse(),
+                _ => match id.asset_id().and_then(|name| assets.get(AssetKind::Sound, name)) {
+                    Some(asset) => decode_clip(&asset.bytes).unwrap_or_else(|e| {
+                        eprintln!("AudioManager: error decodificando '{}': {}", asset.entry.path, e);
+                        synth_placeholder()
+                    }),
+                    None => synth_placeholder(),
+                },
+            };
+            bank.push((id, clip));
+        }
+        let music = assets.of_kind(AssetKind::Music).map(|a| (a.entry.id.clone(), Arc::clone(&a.bytes))).collect();
+        Ok(RodioBackend { stream, bank, music, voices: HashMap::new(), next_id: 0 })
+    }
+
+    fn clip(&self, sound: SoundId) -> Option<&SamplesBuffer> {
+        self.bank.iter().find(|(i, _)| *i == sound).map(|(_, c)| c)
+    }
+
+    fn add(&mut self, sink: Sink, mix: Option<Arc<Mutex<EarMix>>>) -> VoiceId {
+        self.next_id += 1;
+        self.voices.insert(self.next_id, RodioVoice { sink, mix });
+        self.next_id
+    }
+}
+
+impl AudioBackend for RodioBackend {
+    fn play(&mut self, sound: SoundId, volume: f32) -> Option<VoiceId> {
+        let clip = self.clip(sound)?.clone();
+        let sink = Sink::connect_new(self.stream.mixer());
+        sink.set_volume(volume);
+        sink.append(clip);
+        Some(self.add(sink, None))
+    }
+
+    fn play_loop(&mut self, sound: SoundId, volume: f32) -> Option<VoiceId> {
+        let clip = self.clip(sound)?.clone();
+        let mix = Arc::new(Mutex::new(EarMix { left: 1.0, right: 1.0, cutoff: OPEN_CUTOFF }));
+        let shared = Arc::clone(&mix);
+        let source = ChannelVolume::new(clip.repeat_infinite().low_pass(OPEN_CUTOFF), vec![1.0, 1.0])
+            .periodic_access(Duration::from_millis(20), move |src| {
+                if let Ok(mix) = shared.lock() {
+                    src.set_volume(0, mix.left);
+                    src.set_volume(1, mix.right);
+                    src.inner_mut().to_low_pass(mix.cutoff);
+                }
+            });
+        let sink = Sink::connect_new(self.stream.mixer());
+        sink.set_volume(volume);
+        sink.append(source);
+        Some(self.add(sink, Some(mix)))
+    }
+
+    fn has_music(&self, id: &str) -> bool {
+        self.music.contains_key(id)
+    }
+
+    fn play_music(&mut self, id: &str, volume: f32) -> Option<VoiceId> {
+        let bytes = Arc::clone(self.music.get(id)?);
+        let decoder = Decoder::new_looped(Cursor::new(bytes)).map_err(|e| eprintln!("Musica: error decodificando '{}': {}", id, e)).ok()?;
+        let sink = Sink::connect_new(self.stream.mixer());
+        sink.set_volume(volume);
+        sink.append(decoder);
+        Some(self.add(sink, None))
+    }
+
+    fn set_volume(&mut self, voice: VoiceId, volume: f32) {
+        if let Some(v) = self.voices.get(&voice) { v.sink.set_volume(volume); }
+    }
+
+    fn set_spatial(&mut self, voice: VoiceId, left: f32, right: f32, muffled: bool) {
+        let Some(mix) = self.voices.get(&voice).and_then(|v| v.mix.as_ref()) else { return; };
+        if let Ok(mut mix) = mix.lock() {
+            *mix = EarMix { left, right, cutoff: if muffled { MUFFLED_CUTOFF } else { OPEN_CUTOFF } };
+        }
+    }
+
+    fn stop(&mut self, voice: VoiceId) {
+        if let Some(v) = self.voices.remove(&voice) { v.sink.stop(); }
+    }
+
+    fn is_playing(&self, voice: VoiceId) -> bool {
+        self.voices.get(&voice).is_some_and(|v| !v.sink.empty())
+    }
+
+    fn clip_secs(&self, sound: SoundId) -> f32 {
+        self.clip(sound).and_then(|c| c.total_duration()).map(|d| d.as_secs_f32()).unwrap_or(1.0)
+    }
+
+    fn load_asset(&mut self, asset: &Asset) {
+        match asset.entry.kind {
+            AssetKind::Music => { self.music.insert(asset.entry.id.clone(), Arc::clone(&asset.bytes)); }
+            AssetKind::Sound => {
+                let Some(slot) = self.bank.iter_mut().find(|(id, _)| id.asset_id() == Some(asset.entry.id.as_str())) else { return; };
+                match decode_clip(&asset.bytes) {
+                    Ok(clip) => slot.1 = clip,
+                    Err(e) => eprintln!("AudioManager: error decodificando '{}': {}", asset.entry.path, e),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+pub struct NullBackend;
+
+impl AudioBackend for NullBackend {
+    fn play(&mut self, _: SoundId, _: f32) -> Option<VoiceId> { None }
+    fn play_loop(&mut self, _: SoundId, _: f32) -> Option<VoiceId> { None }
+    fn has_music(&self, _: &str) -> bool { false }
+    fn play_music(&mut self, _: &str, _: f32) -> Option<VoiceId> { None }
+    fn set_volume(&mut self, _: VoiceId, _: f32) {}
+    fn set_spatial(&mut self, _: VoiceId, _: f32, _: f32, _: bool) {}
+    fn stop(&mut self, _: VoiceId) {}
+    fn is_playing(&self, _: VoiceId) -> bool { false }
+    fn clip_secs(&self, _: SoundId) -> f32 { 0.0 }
+    fn load_asset(&mut self, _: &Asset) {}
+}
+
+#[cfg(test)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum AudioCall {
+    Play(SoundId, f32),
+    Loop(SoundId, f32),
+    Music(String, f32),
+    Volume(VoiceId, f32),
+    Spatial(VoiceId, f32, f32, bool),
+    Stop(VoiceId),
+}
+
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct RecordingBackend {
+    pub calls: std::rc::Rc<std::cell::RefCell<Vec<AudioCall>>>,
+    active: Vec<VoiceId>,
+    next_id: VoiceId,
+}
+
+#[cfg(test)]
+impl RecordingBackend {
+    fn start(&mut self, call: AudioCall) -> Option<VoiceId> {
+        self.calls.borrow_mut().push(call);
+        self.next_id += 1;
+        self.active.push(self.next_id);
+        Some(self.next_id)
+    }
+}
+
+#[cfg(test)]
+impl AudioBackend for RecordingBackend {
+    fn play(&mut self, sound: SoundId, volume: f32) -> Option<VoiceId> { self.start(AudioCall::Play(sound, volume)) }
+    fn play_loop(&mut self, sound: SoundId, volume: f32) -> Option<VoiceId> { self.start(AudioCall::Loop(sound, volume)) }
+    fn has_music(&self, _: &str) -> bool { true }
+    fn play_music(&mut self, id: &str, volume: f32) -> Option<VoiceId> { self.start(AudioCall::Music(id.to_string(), volume)) }
+    fn set_volume(&mut self, voice: VoiceId, volume: f32) { self.calls.borrow_mut().push(AudioCall::Volume(voice, volume)); }
+    fn set_spatial(&mut self, voice: VoiceId, left: f32, right: f32, muffled: bool) {
+        self.calls.borrow_mut().push(AudioCall::Spatial(voice, left, right, muffled));
+    }
+    fn stop(&mut self, voice: VoiceId) {
+        self.active.retain(|v| *v != voice);
+        self.calls.borrow_mut().push(AudioCall::Stop(voice));
+    }
+    fn is_playing(&self, voice: VoiceId) -> bool { self.active.contains(&voice) }
+    fn clip_secs(&self, _: SoundId) -> f32 { 1.0 }
+    fn load_asset(&mut self, _: &Asset) {}
+}
@@ -1,25 +1,37 @@
 use raylib::color::Color;
-use crate::framebuffer::Framebuffer;
 use crate::player::Player;
-use crate::maze::Maze;
+use crate::maze::{blocks_view, is_see_through, wall_height, Maze, MAX_WALL_HEIGHT};
+use crate::door::{door_at, Door};
 
 pub struct Intersect {
     pub distance: f32,
     pub impact: char,
-    pub hit_x: f32, 
-    pub hit_y: f32,
     pub cell_i: usize,
     pub cell_j: usize,
+    pub tex_u: f32,
 }
 
+pub const MAX_LAYERS: usize = 6;
+
 pub fn cast_ray(
-    _framebuffer: &mut Framebuffer,
     maze: &Maze,
+    doors: &[Door],
     player: &Player,
     angle: f32,
     block_size: usize,
-    _draw_line: bool,
 ) -> Intersect {
+    let mut hits = cast_ray_layers(maze, doors, player, angle, block_size);
+    let i = hits.iter().position(|h| blocks_view(h.impact)).unwrap_or(hits.len() - 1);
+    hits.swap_remove(i)
+}
+
+pub fn cast_ray_layers(
+    maze: &Maze,
+    doors: &[Door],
+    player: &Player,
+    angle: f32,
+    block_size: usize,
+) -> Vec<Intersect> {
     let px = player.pos.x;
     let py = player.pos.y;
     let map_x_f = px / (block_size as f32);
@@ -57,12 +69,54 @@ pub fn cast_ray(
     if delta_dist_x.is_infinite() { side_dist_x = f32::INFINITY; }
     if delta_dist_y.is_infinite() { side_dist_y = f32::INFINITY; }
 
-    let mut hit = false;
-    let mut side = 0; 
+    let intersect = |map_x: i32, map_y: i32, side: i32, door_hit: Option<(f32, f32)>, hit: bool| -> Intersect {
+        let perp_dist = if let Some((t, _)) = door_hit {
+            t * block_size as f32
+        } else if hit {
+            if side == 0 {
+                let offset = (map_x as f32 - map_x_f + ((1 - step_x) as f32) / 2.0);
+                if ray_dir_x.abs() < 1e-6 { (offset).abs() * block_size as f32 } else { offset.abs() / ray_dir_x.abs() * (block_size as f32) }
+            } else {
+                let offset = (map_y as f32 - map_y_f + ((1 - step_y) as f32) / 2.0);
+                if ray_dir_y.abs() < 1e-6 { (offset).abs() * block_size as f32 } else { offset.abs() / ray_dir_y.abs() * (block_size as f32) }
+            }
+        } else {
+            (maze.len() + maze[0].len()) as f32 * block_size as f32
+        };
+
+        let dist_world = perp_dist;
+        let hit_x = px + ray_dir_x * (dist_world);
+        let hit_y = py + ray_dir_y * (dist_world);
+
+        let ci = if map_x < 0 { 0 } else { map_x as usize }.min(maze[0].len().saturating_sub(1));
+        let cj = if map_y < 0 { 0 } else { map_y as usize }.min(maze.len().saturating_sub(1));
+        let impact_char = if cj < maze.len() && ci < maze[0].len() { maze[cj][ci] } else { '+' };
+
+        let tex_u = match door_hit {
+            Some((_, u)) => u,
+            None if side == 0 => (hit_y / block_size as f32).fract(),
+            None => (hit_x / block_size as f32).fract(),
+        };
+
+        Intersect {
+            distance: dist_world.max(0.0),
+            impact: impact_char,
+            cell_i: ci,
+            cell_j: cj,
+            tex_u,
+        }
+    };
+
+    let mut layers = Vec::new();
+    let mut side = 0;
+    let mut occluder = 0.0f32;
     let max_iter = 2000usize;
     let mut iter = 0usize;
+    let eye = player.eye_height();
+    let visible = |height: f32, occluder: f32| height > occluder || occluder < eye;
 
-    while !hit && iter < max_iter {
+    while iter < max_iter {
+        iter += 1;
         if side_dist_x < side_dist_y {
             side_dist_x += delta_dist_x;
             map_x += step_x;
@@ -74,45 +128,54 @@ pub fn cast_ray(
         }
 
         if map_y < 0 || map_x < 0 || (map_y as usize) >= maze.len() || (map_x as usize) >= maze[0].len() {
-            hit = true;
-            break;
+            if visible(1.0, occluder) { layers.push(intersect(map_x, map_y, side, None, true)); }
+            return layers;
         }
 
         let ch = maze[map_y as usize][map_x as usize];
-        if ch != ' ' {
-            hit = true;
-            break;
+        let mut door_hit = None;
+        if ch == 'D' && let Some(door) = door_at(doors, (map_x as usize, map_y as usize)) {
+            door_hit = door.ray_hit(map_x_f, map_y_f, ray_dir_x, ray_dir_y);
+            if door_hit.is_none() { continue; }
+        }
+        if ch == ' ' { continue; }
+        if is_see_through(ch) {
+            if layers.len() < MAX_LAYERS && visible(1.0, occluder) { layers.push(intersect(map_x, map_y, side, None, true)); }
+            continue;
         }
 
-        iter += 1;
+        let height = wall_height(ch);
+        if visible(height, occluder) { layers.push(intersect(map_x, map_y, side, door_hit, true)); }
+        occluder = occluder.max(height);
+        if occluder >= MAX_WALL_HEIGHT { return layers; }
     }
 
-    let perp_dist = if hit {
-        if side == 0 {
-            let offset = (map_x as f32 - map_x_f + ((1 - step_x) as f32) / 2.0);
-            if ray_dir_x.abs() < 1e-6 { (offset).abs() * block_size as f32 } else { offset.abs() / ray_dir_x.abs() * (block_size as f32) }
-        } else {
-            let offset = (map_y as f32 - map_y_f + ((1 - step_y) as f32) / 2.0);
-            if ray_dir_y.abs() < 1e-6 { (offset).abs() * block_size as f32 } else { offset.abs() / ray_dir_y.abs() * (block_size as f32) }
-        }
-    } else {
-        (maze.len() + maze[0].len()) as f32 * block_size as f32
-    };
+    layers.push(intersect(map_x, map_y, side, None, false));
+    layers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rays_collect_see_through_layers_before_the_wall() {
+        let maze: Maze = ["+------+", "|  b t |", "+------+"].iter().map(|r| r.chars().collect()).collect();
+        let p = Player::new(32.0 + 64.0, 96.0, 0.0, std::f32::consts::PI / 3.0);
+        let hits = cast_ray_layers(&maze, &[], &p, 0.0, 64);
+        let chars: Vec<char> = hits.iter().map(|h| h.impact).collect();
+        assert_eq!(chars, vec!['b', 't', '|']);
+        assert!(hits.windows(2).all(|w| w[0].distance < w[1].distance));
+        assert_eq!(cast_ray(&maze, &[], &p, 0.0, 64).impact, '|');
+    }
 
-    let dist_world = perp_dist;
-    let hit_x = px + ray_dir_x * (dist_world);
-    let hit_y = py + ray_dir_y * (dist_world);
-
-    let ci = if map_x < 0 { 0 } else { map_x as usize }.min(maze[0].len().saturating_sub(1));
-    let cj = if map_y < 0 { 0 } else { map_y as usize }.min(maze.len().saturating_sub(1));
-    let impact_char = if cj < maze.len() && ci < maze[0].len() { maze[cj][ci] } else { '+' };
-
-    Intersect {
-        distance: dist_world.max(0.0),
-        impact: impact_char,
-        hit_x,
-        hit_y,
-        cell_i: ci,
-        cell_j: cj,
+    #[test]
+    fn rays_step_past_short_walls_and_find_taller_ones_behind() {
+        let maze: Maze = ["+--------+", "|  l h +P|", "+--------+"].iter().map(|r| r.chars().collect()).collect();
+        let p = Player::new(32.0 + 64.0, 96.0, 0.0, std::f32::consts::PI / 3.0);
+        let hits = cast_ray_layers(&maze, &[], &p, 0.0, 64);
+        let chars: Vec<char> = hits.iter().map(|h| h.impact).collect();
+        assert_eq!(chars, vec!['l', 'h', '+', 'P']);
+        assert_eq!(cast_ray(&maze, &[], &p, 0.0, 64).impact, '+');
     }
 }
@@ -0,0 +1,159 @@
+use raylib::prelude::*;
+use crate::maze::Maze;
+use crate::door::{is_passable, Door};
+
+pub const PLAYER_RADIUS: f32 = 0.25;
+pub const ENEMY_RADIUS: f32 = 0.2;
+
+pub fn is_blocked(maze: &Maze, doors: &[Door], x: isize, y: isize) -> bool {
+    if x < 0 || y < 0 || (y as usize) >= maze.len() || (x as usize) >= maze[y as usize].len() {
+        return true;
+    }
+    match maze[y as usize][x as usize] {
+        ' ' | 'p' | 'g' => false,
+        'D' => !is_passable(doors, (x as usize, y as usize)),
+        _ => true,
+    }
+}
+
+pub fn collides(maze: &Maze, doors: &[Door], pos: Vector2, radius: f32, block_size: usize) -> bool {
+    let bs = block_size as f32;
+    let x0 = ((pos.x - radius) / bs).floor() as isize;
+    let x1 = ((pos.x + radius) / bs).floor() as isize;
+    let y0 = ((pos.y - radius) / bs).floor() as isize;
+    let y1 = ((pos.y + radius) / bs).floor() as isize;
+    for cy in y0..=y1 {
+        for cx in x0..=x1 {
+            if !is_blocked(maze, doors, cx, cy) { continue; }
+            let nx = pos.x.clamp(cx as f32 * bs, (cx + 1) as f32 * bs);
+            let ny = pos.y.clamp(cy as f32 * bs, (cy + 1) as f32 * bs);
+            let (dx, dy) = (pos.x - nx, pos.y - ny);
+            if dx * dx + dy * dy < radius * radius {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+pub fn overlapped_cells(pos: Vector2, radius: f32, block_size: usize) -> Vec<(usize, usize)> {
+    let bs = block_size as f32;
+    let x0 = ((pos.x - radius) / bs).floor().max(0.0) as usize;
+    let x1 = ((pos.x + radius) / bs).floor().max(0.0) as usize;
+    let y0 = ((pos.y - radius) / bs).floor().max(0.0) as usize;
+    let y1 = ((pos.y + radius) / bs).floor().max(0.0) as usize;
+    let mut cells = Vec::new();
+    for cy in y0..=y1 {
+        for cx in x0..=x1 {
+            cells.push((cx, cy));
+        }
+    }
+    cells
+}
+
+pub fn move_and_slide(maze: &Maze, doors: &[Door], pos: &mut Vector2, delta: Vector2, radius: f32, block_size: usize) -> bool {
+    let total = (delta.x * delta.x + delta.y * delta.y).sqrt();
+    if total <= 0.0 { return false; }
+    let max_step = (radius * 0.5).max(1.0).min(block_size as f32 * 0.25);
+    let steps = ((total / max_step).ceil() as usize).max(1);
+    let (sx, sy) = (delta.x / steps as f32, delta.y / steps as f32);
+    let start = *pos;
+
+    for _ in 0..steps {
+        let try_x = Vector2::new(pos.x + sx, pos.y);
+        if !collides(maze, doors, try_x, radius, block_size) {
+            *pos = try_x;
+        }
+        let try_y = Vector2::new(pos.x, pos.y + sy);
+        if !collides(maze, doors, try_y, radius, block_size) {
+            *pos = try_y;
+        }
+    }
+
+    pos.x != start.x || pos.y != start.y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BS: usize = 10;
+
+    fn maze(rows: &[&str]) -> Maze {
+        rows.iter().map(|r| r.chars().collect()).collect()
+    }
+
+    fn center(x: usize, y: usize) -> Vector2 {
+        Vector2::new((x * BS) as f32 + BS as f32 / 2.0, (y * BS) as f32 + BS as f32 / 2.0)
+    }
+
+    #[test]
+    fn open_cell_does_not_collide() {
+        let m = maze(&["+-+", "| |", "+-+"]);
+        assert!(!collides(&m, &[], center(1, 1), 2.5, BS));
+    }
+
+    #[test]
+    fn radius_touching_wall_collides() {
+        let m = maze(&["+-+", "| |", "+-+"]);
+        assert!(collides(&m, &[], Vector2::new(11.0, 15.0), 2.5, BS));
+    }
+
+    #[test]
+    fn stops_short_of_wall_by_radius() {
+        let m = maze(&["+---+", "|   |", "+---+"]);
+        let mut pos = center(1, 1);
+        move_and_slide(&m, &[], &mut pos, Vector2::new(100.0, 0.0), 2.5, BS);
+        assert!(pos.x <= 40.0 - 2.5 + 1e-3);
+        assert!(pos.x > 40.0 - 2.5 - 1.0);
+        assert_eq!(pos.y, 15.0);
+    }
+
+    #[test]
+    fn diagonal_into_wall_slides_along_it() {
+        let m = maze(&["+---+", "|   |", "+---+"]);
+        let mut pos = center(1, 1);
+        let moved = move_and_slide(&m, &[], &mut pos, Vector2::new(10.0, 10.0), 2.5, BS);
+        assert!(moved);
+        assert!((pos.x - 25.0).abs() < 1e-3);
+        assert!(pos.y <= 20.0 - 2.5 + 1e-3);
+    }
+
+    #[test]
+    fn blocked_corner_does_not_move() {
+        let m = maze(&["+-+", "| |", "+-+"]);
+        let mut pos = Vector2::new(17.5, 17.5);
+        let moved = move_and_slide(&m, &[], &mut pos, Vector2::new(5.0, 5.0), 2.5, BS);
+        assert!(!moved);
+    }
+
+    #[test]
+    fn does_not_tunnel_through_thin_wall() {
+        let m = maze(&["+-----+", "| | | |", "+-----+"]);
+        let mut pos = center(1, 1);
+        move_and_slide(&m, &[], &mut pos, Vector2::new(60.0, 0.0), 2.5, BS);
+        assert!(pos.x < 20.0);
+    }
+
+    #[test]
+    fn closed_door_blocks_and_open_door_passes() {
+        let m = maze(&["+---+", "| D |", "+---+"]);
+        let mut door = Door::new(&m, (2, 1), 1, None);
+        let mut pos = center(1, 1);
+        move_and_slide(&m, std::slice::from_ref(&door), &mut pos, Vector2::new(20.0, 0.0), 2.5, BS);
+        assert!(pos.x < 20.0);
+
+        door.state = crate::door::DoorState::Open;
+        door.open = 1.0;
+        move_and_slide(&m, std::slice::from_ref(&door), &mut pos, Vector2::new(20.0, 0.0), 2.5, BS);
+        assert!(pos.x > 30.0);
+    }
+
+    #[test]
+    fn outside_the_map_is_solid() {
+        let m = maze(&["  ", "  "]);
+        assert!(is_blocked(&m, &[], -1, 0));
+        assert!(is_blocked(&m, &[], 0, 2));
+        assert!(!is_blocked(&m, &[], 1, 1));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::maze::Maze;
+
+pub const GOAL_LOCK: u8 = 0;
+pub const DOOR_SPEED: f32 = 1.2;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DoorState {
+    Closed,
+    Opening,
+    Open,
+    Closing,
+}
+
+pub struct Door {
+    pub cell: (usize, usize),
+    pub lock: u8,
+    pub unlocked: bool,
+    pub state: DoorState,
+    pub open: f32,
+    pub horizontal: bool,
+    pub auto_close: Option<f32>,
+    pub timer: f32,
+}
+
+impl Door {
+    pub fn new(maze: &Maze, cell: (usize, usize), lock: u8, auto_close: Option<f32>) -> Self {
+        let solid = |x: isize, y: isize| {
+            if x < 0 || y < 0 || (y as usize) >= maze.len() || (x as usize) >= maze[0].len() { return true; }
+            matches!(maze[y as usize][x as usize], '+' | '-' | '|')
+        };
+        let (x, y) = (cell.0 as isize, cell.1 as isize);
+        let horizontal = solid(x - 1, y) || solid(x + 1, y) || !(solid(x, y - 1) || solid(x, y + 1));
+        Door {
+            cell,
+            lock,
+            unlocked: false,
+            state: DoorState::Closed,
+            open: 0.0,
+            horizontal,
+            auto_close,
+            timer: 0.0,
+        }
+    }
+
+    pub fn is_passable(&self) -> bool { self.state == DoorState::Open }
+
+    // Ray against the thin panel in the middle of the cell. The panel slides towards the
+    // low side of the cell, so the covered span is `[open, 1)`. Coordinates are in cells.
+    pub fn ray_hit(&self, ox: f32, oy: f32, dx: f32, dy: f32) -> Option<(f32, f32)> {
+        let (ci, cj) = (self.cell.0 as f32, self.cell.1 as f32);
+        let (t, u) = if self.horizontal {
+            if dy.abs() < 1e-6 { return None; }
+            let t = (cj + 0.5 - oy) / dy;
+            (t, ox + t * dx - ci)
+        } else {
+            if dx.abs() < 1e-6 { return None; }
+            let t = (ci + 0.5 - ox) / dx;
+            (t, oy + t * dy - cj)
+        };
+        if t < 0.0 || !(0.0..1.0).contains(&u) || u < self.open { return None; }
+        Some((t, u - self.open))
+    }
+
+    pub fn update(&mut self, dt: f32, occupied: bool) {
+        match self.state {
+            DoorState::Opening => {
+                self.open = (self.open + DOOR_SPEED * dt).min(1.0);
+                if self.open >= 1.0 {
+                    self.state = DoorState::Open;
+                    self.timer = self.auto_close.unwrap_or(0.0);
+                }
+            }
+            DoorState::Open => {
+                if self.auto_close.is_some() && !occupied {
+                    self.timer -= dt;
+                    if self.timer <= 0.0 { self.state = DoorState::Closing; }
+                }
+            }
+            DoorState::Closing => {
+                if occupied {
+                    self.state = DoorState::Opening;
+                } else {
+                    self.open = (self.open - DOOR_SPEED * dt).max(0.0);
+                    if self.open <= 0.0 { self.state = DoorState::Closed; }
+                }
+            }
+            DoorState::Closed => {}
+        }
+    }
+}
+
+pub fn door_at(doors: &[Door], cell: (usize, usize)) -> Option<&Door> {
+    doors.iter().find(|d| d.cell == cell)
+}
+
+pub fn lock_at(doors: &[Door], cell: (usize, usize)) -> Option<u8> {
+    door_at(doors, cell).map(|d| d.lock)
+}
+
+pub fn is_passable(doors: &[Door], cell: (usize, usize)) -> bool {
+    door_at(doors, cell).is_none_or(|d| d.is_passable())
+}
+
+pub fn open_door_group(doors: &mut [Door], cell: (usize, usize)) -> Vec<(usize, usize)> {
+    let Some(lock) = lock_at(doors, cell) else { return Vec::new(); };
+    let mut opened = Vec::new();
+    let mut stack = vec![cell];
+    while let Some((x, y)) = stack.pop() {
+        if opened.contains(&(x, y)) { continue; }
+        let Some(door) = doors.iter_mut().find(|d| d.cell == (x, y) && d.lock == lock) else { continue; };
+        door.unlocked = true;
+        if door.state != DoorState::Open { door.state = DoorState::Opening; }
+        opened.push((x, y));
+        stack.push((x + 1, y));
+        stack.push((x, y + 1));
+        if x > 0 { stack.push((x - 1, y)); }
+        if y > 0 { stack.push((x, y - 1)); }
+    }
+    opened
+}
+
+pub fn update_doors(doors: &mut [Door], dt: f32, occupied: &[(usize, usize)]) {
+    for d in doors.iter_mut() {
+        let busy = occupied.contains(&d.cell);
+        d.update(dt, busy);
+    }
+}
@@ -1,12 +1,15 @@
 use raylib::prelude::*;
 use std::collections::VecDeque;
 use crate::maze::Maze;
+use crate::door::{is_passable, Door};
+use crate::collision::{move_and_slide, ENEMY_RADIUS};
 
 pub struct Enemy {
     pub spawn_cell: (usize, usize),
     pub pos: Vector2,
     pub tex_index: usize,
     pub speed: f32,
+    pub radius: f32,
     pub path: Vec<(usize, usize)>,
     pub path_timer: f32,
     pub attack_cooldown: f32,
@@ -21,6 +24,7 @@ impl Enemy {
             pos: Vector2::new(cx, cy),
             tex_index,
             speed,
+            radius: block_size as f32 * ENEMY_RADIUS,
             path: Vec::new(),
             path_timer: 0.0,
             attack_cooldown: 0.0,
@@ -43,7 +47,7 @@ impl Enemy {
         (cx, cy)
     }
 
-    fn bfs_path(maze: &Maze, start: (usize, usize), target: (usize, usize)) -> Vec<(usize, usize)> {
+    fn bfs_path(maze: &Maze, doors: &[Door], start: (usize, usize), target: (usize, usize)) -> Vec<(usize, usize)> {
         let rows = maze.len();
         let cols = maze[0].len();
         let mut q = VecDeque::new();
@@ -65,7 +69,7 @@ impl Enemy {
                     let uy = ny as usize;
                     if !visited[uy][ux] {
                         let ch = maze[uy][ux];
-                        if ch == ' ' || ch == 'p' || ch == 'g' {
+                        if ch == ' ' || ch == 'p' || ch == 'g' || (ch == 'D' && is_passable(doors, (ux, uy))) {
                             visited[uy][ux] = true;
                             parent[uy][ux] = Some((cx, cy));
                             q.push_back((ux, uy));
@@ -92,7 +96,7 @@ impl Enemy {
         path
     }
 
-    pub fn update(&mut self, maze: &Maze, block_size: usize, player_pos: &Vector2, dt: f32) -> bool {
+    pub fn update(&mut self, maze: &Maze, doors: &[Door], block_size: usize, player_pos: &Vector2, dt: f32) -> bool {
         if self.attack_cooldown > 0.0 {
             self.attack_cooldown -= dt;
         }
@@ -102,10 +106,14 @@ impl Enemy {
         let my_cell = self.current_cell(block_size, maze);
 
         if self.path_timer <= 0.0 || self.path.is_empty() {
-            self.path = Enemy::bfs_path(maze, my_cell, player_cell);
+            self.path = Enemy::bfs_path(maze, doors, my_cell, player_cell);
             self.path_timer = 1.0; 
         }
 
+        if self.path.len() >= 2 && !is_passable(doors, self.path[1]) {
+            self.path.clear();
+        }
+
         if self.path.len() >= 2 {
             let next = self.path[1];
             let target_x = (next.0 * block_size) as f32 + (block_size as f32) / 2.0;
@@ -115,8 +123,10 @@ impl Enemy {
             let dist = (dx*dx + dy*dy).sqrt();
             if dist > 1.0 {
                 let step = (self.speed * dt).min(dist);
-                self.pos.x += dx / dist * step;
-                self.pos.y += dy / dist * step;
+                let delta = Vector2::new(dx / dist * step, dy / dist * step);
+                if !move_and_slide(maze, doors, &mut self.pos, delta, self.radius, block_size) {
+                    self.path.clear();
+                }
             } else {
                 if !self.path.is_empty() { self.path.remove(0); }
             }
@@ -0,0 +1,71 @@
+use crate::inventory::Item;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    Footstep,
+    Hurt(f32),
+    PickedUp(Item),
+    ItemUsed(Item),
+    KeyNotUsable,
+    DoorOpened(u8),
+    DoorLocked(u8),
+    GoalLocked,
+    Victory,
+    Death,
+}
+
+pub trait Subscriber {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+#[derive(Default)]
+pub struct EventBus {
+    queue: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn publish_all(&mut self, events: impl IntoIterator<Item = GameEvent>) {
+        self.queue.extend(events);
+    }
+
+    pub fn dispatch(&mut self, subscribers: &mut [&mut dyn Subscriber]) -> Vec<GameEvent> {
+        let events = std::mem::take(&mut self.queue);
+        for event in events.iter() {
+            for sub in subscribers.iter_mut() {
+                sub.on_event(event);
+            }
+        }
+        events
+    }
+}
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct Stats {
+    pub steps: u32,
+    pub damage_taken: f32,
+    pub items_picked: u32,
+    pub items_used: u32,
+    pub doors_opened: u32,
+}
+
+impl Stats {
+    pub fn summary(&self) -> String {
+        format!(
+            "Pasos: {}  Dano recibido: {:.0}  Objetos: {}  Usados: {}  Puertas: {}",
+            self.steps, self.damage_taken, self.items_picked, self.items_used, self.doors_opened
+        )
+    }
+}
+
+impl Subscriber for Stats {
+    fn on_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Footstep => self.steps += 1,
+            GameEvent::Hurt(amount) => self.damage_taken += amount,
+            GameEvent::PickedUp(_) => self.items_picked += 1,
+            GameEvent::ItemUsed(_) => self.items_used += 1,
+            GameEvent::DoorOpened(_) => self.doors_opened += 1,
+            _ => {}
+        }
+    }
+}
@@ -1,5 +1,19 @@
 use raylib::prelude::*;
 use raylib::{RaylibHandle, RaylibThread};
+use crate::inventory::{key_color, Item, ItemStack};
+
+pub type SpriteDraw<'a> = (&'a Texture2D, Rectangle, Rectangle, Color);
+
+#[derive(Default)]
+pub struct Hud<'a> {
+    pub fps_text: Option<&'a str>,
+    pub stamina: Option<(f32, f32)>,
+    pub health: Option<(f32, f32)>,
+    pub shield: Option<(f32, f32)>,
+    pub inventory: Option<(&'a [ItemStack], usize)>,
+    pub inventory_hint: Option<&'a str>,
+    pub prompt: Option<&'a str>,
+}
 
 pub struct Framebuffer {
     width: u32,
@@ -52,24 +66,20 @@ impl Framebuffer {
         &self,
         window: &mut RaylibHandle,
         raylib_thread: &RaylibThread,
-        fps_text: Option<&str>,
-        stamina_opt: Option<(f32, f32)>,
-        health_opt: Option<(f32, f32)>,
-        shield_opt: Option<(f32, f32)>,
-        sprite_draws: Option<&[(&Texture2D, f32, i32, i32)]>,
+        sprite_draws: Option<&[SpriteDraw]>,
+        hud: &Hud,
     ) {
         if let Ok(texture) = window.load_texture_from_image(raylib_thread, &self.color_buffer) {
             let mut d = window.begin_drawing(raylib_thread);
             d.draw_texture(&texture, 0, 0, Color::WHITE);
 
             if let Some(list) = sprite_draws {
-                for &(tex, scale, sx, sy) in list.iter() {
-                    let pos = Vector2::new(sx as f32, sy as f32);
-                    d.draw_texture_ex(tex, pos, 0.0, scale, Color::WHITE);
+                for &(tex, src, dest, tint) in list.iter() {
+                    d.draw_texture_pro(tex, src, dest, Vector2::zero(), 0.0, tint);
                 }
             }
 
-            if let Some(fps) = fps_text {
+            if let Some(fps) = hud.fps_text {
                 let margin = 10;
                 let font_size = 20;
                 let tw = d.measure_text(fps, font_size);
@@ -78,7 +88,7 @@ impl Framebuffer {
                 d.draw_text(fps, tx, ty, font_size, Color::DARKGRAY);
             }
 
-            if let Some((current, max)) = stamina_opt {
+            if let Some((current, max)) = hud.stamina {
                 let margin = 10;
                 let bar_w = 300;
                 let bar_h = 18;
@@ -96,7 +106,7 @@ impl Framebuffer {
                 d.draw_text(label, x + (bar_w/2) - (ltw/2), y + bar_h + 2, 16, Color::DARKGRAY);
             }
 
-            if let Some((current, max)) = health_opt {
+            if let Some((current, max)) = hud.health {
                 let margin = 10;
                 let bar_w = 200;
                 let bar_h = 18;
@@ -112,7 +122,7 @@ impl Framebuffer {
                 let label = format!("HP: {}/{}", current.round() as i32, max.round() as i32);
                 d.draw_text(&label, x + bar_w + 8, y, 18, Color::DARKGRAY);
 
-                if let Some((scur, smax)) = shield_opt {
+                if let Some((scur, smax)) = hud.shield {
                     if scur > 0.0 {
                         let sy = y + bar_h + 6;
                         d.draw_rectangle(x, sy, bar_w, bar_h, Color::LIGHTGRAY);
@@ -125,6 +135,49 @@ impl Framebuffer {
                     }
                 }
             }
+
+            if let Some((slots, active)) = hud.inventory {
+                let slot_w = 150;
+                let slot_h = 44;
+                let gap = 8;
+                let margin = 14;
+                let n = slots.len().max(1) as i32;
+                let total_w = n * slot_w + (n - 1) * gap;
+                let mut x = ((self.width as i32) - total_w) / 2;
+                let y = (self.height as i32) - slot_h - margin - 20;
+
+                if slots.is_empty() {
+                    d.draw_rectangle(x, y, slot_w, slot_h, Color::new(0, 0, 0, 120));
+                    d.draw_rectangle_lines(x, y, slot_w, slot_h, Color::DARKGRAY);
+                    d.draw_text("Vacio", x + 10, y + 12, 18, Color::LIGHTGRAY);
+                }
+                for (i, stack) in slots.iter().enumerate() {
+                    let border = if i == active { Color::RED } else { Color::DARKGRAY };
+                    d.draw_rectangle(x, y, slot_w, slot_h, Color::new(0, 0, 0, 120));
+                    if let Item::Key(id) = stack.item {
+                        d.draw_rectangle(x + 6, y + 6, 10, slot_h - 12, key_color(id));
+                    }
+                    d.draw_rectangle_lines(x, y, slot_w, slot_h, border);
+                    if i == active { d.draw_rectangle_lines(x + 1, y + 1, slot_w - 2, slot_h - 2, border); }
+                    d.draw_text(&stack.item.label(), x + 22, y + 6, 16, Color::RAYWHITE);
+                    d.draw_text(&format!("x{}", stack.count), x + 22, y + 24, 16, Color::LIGHTGRAY);
+                    x += slot_w + gap;
+                }
+
+                if let Some(hint) = hud.inventory_hint {
+                    let hw = d.measure_text(hint, 16);
+                    d.draw_text(hint, ((self.width as i32) - hw) / 2, y + slot_h + 4, 16, Color::DARKGRAY);
+                }
+            }
+
+            if let Some(text) = hud.prompt {
+                let font_size = 24;
+                let tw = d.measure_text(text, font_size);
+                let x = ((self.width as i32) - tw) / 2;
+                let y = (self.height as i32) / 2 + 40;
+                d.draw_rectangle(x - 10, y - 6, tw + 20, font_size + 12, Color::new(0, 0, 0, 140));
+                d.draw_text(text, x, y, font_size, Color::RAYWHITE);
+            }
         }
     }
 
@@ -133,6 +186,6 @@ impl Framebuffer {
         window: &mut RaylibHandle,
         raylib_thread: &RaylibThread,
     ) {
-        self.swap_buffers_with_fps(window, raylib_thread, None, None, None, None, None);
+        self.swap_buffers_with_fps(window, raylib_thread, None, &Hud::default());
     }
 }
@@ -1,11 +1,11 @@
-use rand::{thread_rng, seq::SliceRandom};
-use crate::maze::Maze;
+use rand::{Rng, seq::SliceRandom};
+use std::collections::VecDeque;
+use crate::maze::{Maze, PILLAR};
 
 
-pub fn generate_maze_text(width_cells: usize, height_cells: usize) -> Maze {
+pub fn generate_maze_text(width_cells: usize, height_cells: usize, rng: &mut impl Rng) -> Maze {
     let mut visited = vec![vec![false; width_cells]; height_cells];
     let mut stack = Vec::new();
-    let mut rng = thread_rng();
 
     let dirs = [(1isize,0isize), (-1,0), (0,1), (0,-1)];
 
@@ -26,7 +26,7 @@ pub fn generate_maze_text(width_cells: usize, height_cells: usize) -> Maze {
 
         if !neighbors.is_empty() {
             stack.push((cx, cy));
-            let &(nx, ny) = neighbors.choose(&mut rng).unwrap();
+            let &(nx, ny) = neighbors.choose(rng).unwrap();
             visited[ny][nx] = true;
             stack.push((nx, ny));
         }
@@ -69,7 +69,7 @@ pub fn generate_maze_text(width_cells: usize, height_cells: usize) -> Maze {
 
         if !neighbors.is_empty() {
             stack2.push((cx, cy));
-            let &(nx, ny) = neighbors.choose(&mut rng).unwrap();
+            let &(nx, ny) = neighbors.choose(rng).unwrap();
 
             let gx = cx * 2 + 1;
             let gy = cy * 2 + 1;
@@ -87,3 +87,174 @@ pub fn generate_maze_text(width_cells: usize, height_cells: usize) -> Maze {
 
     grid
 }
+
+pub struct LockLayout {
+    pub doors: Vec<((usize, usize), u8)>,
+    pub keys: Vec<((usize, usize), u8)>,
+}
+
+fn reachable_cells(maze: &Maze, start: (usize, usize), closed: &[(usize, usize)]) -> Vec<Vec<bool>> {
+    let rows = maze.len();
+    let cols = maze[0].len();
+    let mut seen = vec![vec![false; cols]; rows];
+    let mut q = VecDeque::new();
+    seen[start.1][start.0] = true;
+    q.push_back(start);
+    let dirs = [(1isize,0isize), (-1,0), (0,1), (0,-1)];
+    while let Some((cx, cy)) = q.pop_front() {
+        for &(dx, dy) in dirs.iter() {
+            let nx = cx as isize + dx;
+            let ny = cy as isize + dy;
+            if nx < 0 || ny < 0 || (nx as usize) >= cols || (ny as usize) >= rows { continue; }
+            let (ux, uy) = (nx as usize, ny as usize);
+            if seen[uy][ux] || closed.contains(&(ux, uy)) { continue; }
+            if matches!(maze[uy][ux], ' ' | 'p' | 'g' | 'D') {
+                seen[uy][ux] = true;
+                q.push_back((ux, uy));
+            }
+        }
+    }
+    seen
+}
+
+fn path_between(maze: &Maze, start: (usize, usize), target: (usize, usize)) -> Vec<(usize, usize)> {
+    let rows = maze.len();
+    let cols = maze[0].len();
+    let mut parent = vec![vec![None; cols]; rows];
+    let mut seen = vec![vec![false; cols]; rows];
+    let mut q = VecDeque::new();
+    seen[start.1][start.0] = true;
+    q.push_back(start);
+    let dirs = [(1isize,0isize), (-1,0), (0,1), (0,-1)];
+    while let Some((cx, cy)) = q.pop_front() {
+        if (cx, cy) == target { break; }
+        for &(dx, dy) in dirs.iter() {
+            let nx = cx as isize + dx;
+            let ny = cy as isize + dy;
+            if nx < 0 || ny < 0 || (nx as usize) >= cols || (ny as usize) >= rows { continue; }
+            let (ux, uy) = (nx as usize, ny as usize);
+            if !seen[uy][ux] && matches!(maze[uy][ux], ' ' | 'p' | 'g' | 'D') {
+                seen[uy][ux] = true;
+                parent[uy][ux] = Some((cx, cy));
+                q.push_back((ux, uy));
+            }
+        }
+    }
+    let mut path = Vec::new();
+    if !seen[target.1][target.0] { return path; }
+    let mut cur = target;
+    path.push(cur);
+    while let Some(prev) = parent[cur.1][cur.0] {
+        cur = prev;
+        path.push(cur);
+    }
+    path.reverse();
+    path
+}
+
+// Doors go on corridor blocks that were connectors in the unexpanded maze, so each one cuts the
+// spawn->goal path. Key `i` is always placed in the area that only needs keys `< i` to reach.
+pub fn place_locks(
+    maze: &mut Maze,
+    factor: usize,
+    start: (usize, usize),
+    goal: (usize, usize),
+    goal_doors: &[(usize, usize)],
+    count: usize,
+    rng: &mut impl Rng,
+) -> LockLayout {
+    let path = path_between(maze, start, goal);
+
+    let mut blocks: Vec<(usize, usize)> = Vec::new();
+    for &(x, y) in path.iter() {
+        let (rx, ry) = (x / factor, y / factor);
+        if (rx % 2 == 0) == (ry % 2 == 0) || blocks.contains(&(rx, ry)) { continue; }
+        let cells_open = (0..factor).all(|fy| (0..factor).all(|fx| maze[ry * factor + fy][rx * factor + fx] == ' '));
+        let has_start = start.0 / factor == rx && start.1 / factor == ry;
+        if cells_open && !has_start { blocks.push((rx, ry)); }
+    }
+
+    let count = count.min(blocks.len());
+    let mut chosen: Vec<(usize, usize)> = Vec::new();
+    for i in 0..count {
+        let idx = ((i + 1) * blocks.len()) / (count + 1);
+        if !chosen.contains(&blocks[idx]) { chosen.push(blocks[idx]); }
+    }
+
+    let mut doors: Vec<((usize, usize), u8)> = Vec::new();
+    for (i, &(rx, ry)) in chosen.iter().enumerate() {
+        let lock = (i + 1) as u8;
+        for fy in 0..factor {
+            for fx in 0..factor {
+                let cell = (rx * factor + fx, ry * factor + fy);
+                maze[cell.1][cell.0] = 'D';
+                doors.push((cell, lock));
+            }
+        }
+    }
+
+    let on_path = |c: (usize, usize)| path.contains(&c);
+    let mut keys = Vec::new();
+    let mut prev_region: Option<Vec<Vec<bool>>> = None;
+    for step in 0..=chosen.len() {
+        let mut closed: Vec<(usize, usize)> = goal_doors.to_vec();
+        closed.extend(doors.iter().filter(|(_, l)| (*l as usize) > step).map(|(c, _)| *c));
+        let region = reachable_cells(maze, start, &closed);
+
+        let mut fresh = Vec::new();
+        let mut any = Vec::new();
+        for (y, row) in region.iter().enumerate() {
+            for (x, &seen) in row.iter().enumerate() {
+                if !seen || maze[y][x] != ' ' || (x, y) == start { continue; }
+                any.push((x, y));
+                if prev_region.as_ref().is_some_and(|p| p[y][x]) { continue; }
+                fresh.push((x, y));
+            }
+        }
+        let off_path: Vec<(usize, usize)> = fresh.iter().copied().filter(|&c| !on_path(c)).collect();
+        let pool = if !off_path.is_empty() { off_path } else if !fresh.is_empty() { fresh } else { any };
+
+        let lock = if step == chosen.len() { 0 } else { (step + 1) as u8 };
+        if let Some(&cell) = pool.choose(rng) {
+            keys.push((cell, lock));
+        }
+        prev_region = Some(region);
+    }
+
+    LockLayout { doors, keys }
+}
+
+
+pub fn place_wall_variants(maze: &mut Maze, count: usize, variants: &[char], rng: &mut impl Rng) {
+    let rows = maze.len();
+    let cols = maze[0].len();
+    let open = |ch: char| matches!(ch, ' ' | 'p' | 'g');
+    let mut thin: Vec<(usize, usize)> = Vec::new();
+    for y in 1..rows.saturating_sub(1) {
+        for x in 1..cols.saturating_sub(1) {
+            let between = match maze[y][x] {
+                '-' => open(maze[y - 1][x]) && open(maze[y + 1][x]),
+                '|' => open(maze[y][x - 1]) && open(maze[y][x + 1]),
+                _ => false,
+            };
+            if between { thin.push((x, y)); }
+        }
+    }
+    thin.shuffle(rng);
+    for &(x, y) in thin.iter().take(count) {
+        maze[y][x] = *variants.choose(rng).unwrap();
+    }
+}
+
+pub fn place_pillars(maze: &mut Maze, count: usize, rng: &mut impl Rng) {
+    let rows = maze.len();
+    let cols = maze[0].len();
+    let mut posts: Vec<(usize, usize)> = Vec::new();
+    for y in (2..rows.saturating_sub(2)).step_by(2) {
+        for x in (2..cols.saturating_sub(2)).step_by(2) {
+            if maze[y][x] == '+' { posts.push((x, y)); }
+        }
+    }
+    posts.shuffle(rng);
+    for &(x, y) in posts.iter().take(count) { maze[y][x] = PILLAR; }
+}
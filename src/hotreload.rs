@@ -0,0 +1,64 @@
+use std::fs;
+use std::time::SystemTime;
+use crate::assets::AssetEntry;
+
+const POLL_SECS: f32 = 0.5;
+
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+pub struct AssetWatcher {
+    files: Vec<(AssetEntry, Option<SystemTime>)>,
+    timer: f32,
+}
+
+impl AssetWatcher {
+    pub fn new(entries: &[AssetEntry]) -> Self {
+        let files = entries.iter().map(|e| (e.clone(), modified(&e.path))).collect();
+        AssetWatcher { files, timer: POLL_SECS }
+    }
+
+    pub fn poll(&mut self, dt: f32) -> Vec<AssetEntry> {
+        self.timer -= dt;
+        if self.timer > 0.0 { return Vec::new(); }
+        self.timer = POLL_SECS;
+        self.changed()
+    }
+
+    fn changed(&mut self) -> Vec<AssetEntry> {
+        let mut changed = Vec::new();
+        for (entry, stamp) in self.files.iter_mut() {
+            let now = modified(&entry.path);
+            if now == *stamp { continue; }
+            *stamp = now;
+            if now.is_some() { changed.push(entry.clone()); }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::parse_manifest;
+    use std::time::Duration;
+
+    #[test]
+    fn reports_files_whose_mtime_changed() {
+        let path = std::env::temp_dir().join(format!("mansion_watch_test_{}.png", std::process::id()));
+        fs::write(&path, b"a").unwrap();
+        let entries = parse_manifest(&format!("sprite a {}\nsprite b no_existe/b.png\n", path.display()));
+        let mut watcher = AssetWatcher::new(&entries);
+        assert!(watcher.poll(0.1).is_empty());
+        assert!(watcher.changed().is_empty());
+
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        let changed = watcher.poll(POLL_SECS);
+        let _ = fs::remove_file(&path);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].id, "a");
+        assert!(watcher.changed().is_empty());
+    }
+}
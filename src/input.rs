@@ -1,137 +1,258 @@
 use raylib::prelude::*;
 use std::f32::consts::PI;
-use crate::player::Player;
+use crate::player::{Player, MAX_PITCH, STEP_INTERVAL};
 use crate::maze::Maze;
-use crate::audio::AudioManager;
+use crate::events::GameEvent;
+use crate::inventory::Item;
+use crate::door::Door;
+use crate::collision::move_and_slide;
+use crate::actions::{Action, Keymap, AXIS_THRESHOLD, GAMEPAD};
+use crate::settings::keymap_path;
 
-pub fn process_events(
-    window: &mut raylib::prelude::RaylibHandle,
+pub struct LookOptions {
+    pub sensitivity: f32,
+    pub invert: bool,
+    pub captured: bool,
+}
+
+impl Default for LookOptions {
+    fn default() -> Self {
+        LookOptions { sensitivity: 0.003, invert: false, captured: false }
+    }
+}
+
+pub struct GamepadOptions {
+    pub deadzone: f32,
+    pub turn_speed: f32,
+}
+
+impl Default for GamepadOptions {
+    fn default() -> Self {
+        GamepadOptions { deadzone: 0.2, turn_speed: 2.8 }
+    }
+}
+
+pub struct Controls {
+    pub keymap: Keymap,
+    pub look: LookOptions,
+    pub pad: GamepadOptions,
+    axes_held: Vec<Action>,
+    axes_prev: Vec<Action>,
+}
+
+impl Controls {
+    pub fn load() -> Self {
+        Controls {
+            keymap: Keymap::load(&keymap_path()),
+            look: LookOptions::default(),
+            pad: GamepadOptions::default(),
+            axes_held: Vec::new(),
+            axes_prev: Vec::new(),
+        }
+    }
+
+    pub fn poll(&mut self, window: &RaylibHandle) {
+        self.axes_prev = std::mem::take(&mut self.axes_held);
+        if !window.is_gamepad_available(GAMEPAD) { return; }
+        for action in Action::ALL.iter() {
+            let Some((axis, sign)) = action.axis() else { continue; };
+            if window.get_gamepad_axis_movement(GAMEPAD, axis) * sign > AXIS_THRESHOLD {
+                self.axes_held.push(*action);
+            }
+        }
+    }
+
+    pub fn stick(&self, window: &RaylibHandle, x_axis: GamepadAxis, y_axis: GamepadAxis) -> (f32, f32) {
+        if !window.is_gamepad_available(GAMEPAD) { return (0.0, 0.0); }
+        let x = window.get_gamepad_axis_movement(GAMEPAD, x_axis);
+        let y = window.get_gamepad_axis_movement(GAMEPAD, y_axis);
+        let len = (x*x + y*y).sqrt();
+        let dz = self.pad.deadzone;
+        if len <= dz { return (0.0, 0.0); }
+        let scaled = ((len - dz) / (1.0 - dz)).min(1.0);
+        (x / len * scaled, y / len * scaled)
+    }
+
+    pub fn down(&self, window: &RaylibHandle, action: Action) -> bool {
+        self.keymap.down(window, action) || self.axes_held.contains(&action)
+    }
+
+    pub fn pressed(&self, window: &RaylibHandle, action: Action) -> bool {
+        self.keymap.pressed(window, action) || (self.axes_held.contains(&action) && !self.axes_prev.contains(&action))
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct InputFrame {
+    pub forward: f32,
+    pub strafe: f32,
+    pub turn: f32,
+    pub look: f32,
+    pub tilt: f32,
+    pub pitch: f32,
+    pub sprint: bool,
+    pub crouch: bool,
+    pub use_target: bool,
+    pub use_item: bool,
+    pub next_item: bool,
+    pub prev_item: bool,
+}
+
+impl InputFrame {
+    pub fn merge(&mut self, newer: InputFrame) {
+        self.forward = newer.forward;
+        self.strafe = newer.strafe;
+        self.turn = newer.turn;
+        self.tilt = newer.tilt;
+        self.sprint = newer.sprint;
+        self.crouch = newer.crouch;
+        self.look += newer.look;
+        self.pitch += newer.pitch;
+        self.use_target |= newer.use_target;
+        self.use_item |= newer.use_item;
+        self.next_item |= newer.next_item;
+        self.prev_item |= newer.prev_item;
+    }
+
+    pub fn take_tick(&mut self) -> InputFrame {
+        let frame = *self;
+        self.look = 0.0;
+        self.pitch = 0.0;
+        self.use_target = false;
+        self.use_item = false;
+        self.next_item = false;
+        self.prev_item = false;
+        frame
+    }
+}
+
+impl Controls {
+    pub fn sample(&self, window: &RaylibHandle) -> InputFrame {
+        const ROT_SPEED: f32 = PI;
+
+        let mut forward = 0.0f32;
+        let mut strafe = 0.0f32;
+        if self.keymap.down(window, Action::MoveForward) { forward += 1.0; }
+        if self.keymap.down(window, Action::MoveBack) { forward -= 1.0; }
+        if self.keymap.down(window, Action::StrafeRight) { strafe += 1.0; }
+        if self.keymap.down(window, Action::StrafeLeft) { strafe -= 1.0; }
+        let (stick_x, stick_y) = self.stick(window, GamepadAxis::GAMEPAD_AXIS_LEFT_X, GamepadAxis::GAMEPAD_AXIS_LEFT_Y);
+
+        let mut turn = 0.0f32;
+        if self.keymap.down(window, Action::TurnLeft) { turn -= ROT_SPEED; }
+        if self.keymap.down(window, Action::TurnRight) { turn += ROT_SPEED; }
+        let (stick_turn, stick_pitch) = self.stick(window, GamepadAxis::GAMEPAD_AXIS_RIGHT_X, GamepadAxis::GAMEPAD_AXIS_RIGHT_Y);
+        turn += stick_turn * stick_turn.abs() * self.pad.turn_speed;
+
+        let mut tilt = -stick_pitch * stick_pitch.abs();
+        if self.keymap.down(window, Action::LookUp) { tilt += 1.0; }
+        if self.keymap.down(window, Action::LookDown) { tilt -= 1.0; }
+
+        let mut look = 0.0f32;
+        let mut pitch = 0.0f32;
+        if self.look.captured {
+            let sign = if self.look.invert { -1.0 } else { 1.0 };
+            let delta = window.get_mouse_delta();
+            look = delta.x * self.look.sensitivity;
+            pitch = -delta.y * self.look.sensitivity * 0.5 * sign;
+        }
+
+        InputFrame {
+            forward: (forward - stick_y).clamp(-1.0, 1.0),
+            strafe: (strafe + stick_x).clamp(-1.0, 1.0),
+            turn,
+            look,
+            tilt: tilt.clamp(-1.0, 1.0),
+            pitch,
+            sprint: self.down(window, Action::Sprint),
+            crouch: self.down(window, Action::Crouch),
+            use_target: self.pressed(window, Action::Use),
+            use_item: self.pressed(window, Action::UseItem),
+            next_item: self.pressed(window, Action::NextItem),
+            prev_item: self.pressed(window, Action::PrevItem),
+        }
+    }
+}
+
+pub fn apply_input(
     player: &mut Player,
-    maze: &mut Maze,
+    maze: &Maze,
+    doors: &[Door],
     block_size: usize,
     dt: f32,
-    goal_unlocked: &mut bool,
-    audio: &AudioManager,
-) -> Option<String> {
+    frame: &InputFrame,
+    events: &mut Vec<GameEvent>,
+) {
     const BASE_SPEED: f32 = 80.0; 
     const RUN_MULT: f32 = 1.6;
-    const RUN_MULT2: f32 = 0.9;
-    const ROT_SPEED: f32 = PI;
-    const MOUSE_SENS: f32 = 0.01;
-
-    let dt = dt.min(0.05);
-    let mut msg: Option<String> = None;
+    const STRAFE_MULT: f32 = 0.85;
+    const CROUCH_MULT: f32 = 0.5;
+    const CROUCH_RATE: f32 = 6.0;
+    const PITCH_SPEED: f32 = 0.6;
 
-    if window.is_key_down(KeyboardKey::KEY_LEFT) {
-        player.a -= ROT_SPEED * dt;
+    if frame.next_item {
+        player.inventory.cycle(1);
     }
-    if window.is_key_down(KeyboardKey::KEY_RIGHT) {
-        player.a += ROT_SPEED * dt;
+    if frame.prev_item {
+        player.inventory.cycle(-1);
     }
-
-    if window.is_mouse_button_down(raylib::consts::MouseButton::MOUSE_BUTTON_RIGHT) {
-        let md = window.get_mouse_delta();
-        player.a += md.x * MOUSE_SENS;
+    if frame.use_item {
+        if matches!(player.inventory.active_item(), Some(Item::Key(_))) {
+            events.push(GameEvent::KeyNotUsable);
+        } else if let Some(item) = player.use_active_item() {
+            events.push(GameEvent::ItemUsed(item));
+        }
     }
 
+    player.a += frame.turn * dt + frame.look;
+    player.pitch = (player.pitch + frame.tilt * PITCH_SPEED * dt + frame.pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    let crouch_target = if frame.crouch { 1.0 } else { 0.0 };
+    let crouch_step = CROUCH_RATE * dt;
+    player.crouch = if player.crouch < crouch_target {
+        (player.crouch + crouch_step).min(crouch_target)
+    } else {
+        (player.crouch - crouch_step).max(crouch_target)
+    };
+
+    let forward = frame.forward;
+    let strafe = frame.strafe * STRAFE_MULT;
+    let moving = forward != 0.0 || strafe != 0.0;
+
     let mut speed = BASE_SPEED;
-    let running = (window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || window.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT)) && player.stamina > 5.0;
-    let running2 = window.is_mouse_button_down(raylib::consts::MouseButton::MOUSE_BUTTON_LEFT) && player.stamina > 5.0;
-    if running{
+    let running = frame.sprint && !frame.crouch && player.stamina > 5.0;
+    if running && moving {
         speed *= RUN_MULT;
         player.stamina = (player.stamina - 60.0 * dt).max(0.0);
         if player.stamina <= 10.0 {
             speed *= 0.5;
         }
-    } if running2{
-        speed *= RUN_MULT2;
-        player.stamina = (player.stamina - 60.0 * dt).max(0.0);
-        if player.stamina <= 10.0 {
-            speed *= 0.5;
-        }
     }
+    speed *= 1.0 - (1.0 - CROUCH_MULT) * player.crouch;
 
-    let mut want_dx = 0.0f32;
-    let mut want_dy = 0.0f32;
-
-    if window.is_key_down(KeyboardKey::KEY_UP) {
-        want_dx += player.a.cos() * speed * dt;
-        want_dy += player.a.sin() * speed * dt;
-    }
-    if window.is_key_down(KeyboardKey::KEY_DOWN) {
-        want_dx -= player.a.cos() * speed * dt;
-        want_dy -= player.a.sin() * speed * dt;
-    }
-    if window.is_mouse_button_down(raylib::consts::MouseButton::MOUSE_BUTTON_LEFT) {
-        want_dx += player.a.cos() * speed * dt;
-        want_dy += player.a.sin() * speed * dt;
+    let (cos, sin) = (player.a.cos(), player.a.sin());
+    let mut want_dx = forward * cos - strafe * sin;
+    let mut want_dy = forward * sin + strafe * cos;
+    let len = (want_dx*want_dx + want_dy*want_dy).sqrt();
+    if len > 1.0 {
+        want_dx /= len;
+        want_dy /= len;
     }
+    want_dx *= speed * dt;
+    want_dy *= speed * dt;
 
     let total_dist = (want_dx*want_dx + want_dy*want_dy).sqrt();
     if total_dist > 0.0 {
-        let max_step = (block_size as f32) * 0.25;
-        let steps = ((total_dist / max_step).ceil() as usize).max(1);
-        let step_dx = want_dx / steps as f32;
-        let step_dy = want_dy / steps as f32;
-
-        for _ in 0..steps {
-            let new_x = player.pos.x + step_dx;
-            let new_y = player.pos.y + step_dy;
+        move_and_slide(maze, doors, &mut player.pos, Vector2::new(want_dx, want_dy), player.radius, block_size);
 
-            let ci = (new_x as isize / block_size as isize) as isize;
-            let cj = (new_y as isize / block_size as isize) as isize;
-
-            if cj < 0 || ci < 0 || (cj as usize) >= maze.len() || (ci as usize) >= maze[0].len() {
-                break;
-            }
-
-            match maze[cj as usize][ci as usize] {
-                ' ' | 'p' => {
-                    player.pos.x = new_x;
-                    player.pos.y = new_y;
-                },
-                'g' => {
-                    player.pos.x = new_x;
-                    player.pos.y = new_y;
-                },
-                'D' => {
-                    if player.has_key {
-                        maze[cj as usize][ci as usize] = ' ';
-                        player.has_key = false;
-                        msg = Some("Usaste la llave para abrir una puerta.".to_string());
-                        let neigh = [(1,0),(-1,0),(0,1),(0,-1)];
-                        for &(dxg, dyg) in neigh.iter() {
-                            let nx = ci + dxg;
-                            let ny = cj + dyg;
-                            if ny >= 0 && nx >= 0 && (ny as usize) < maze.len() && (nx as usize) < maze[0].len() {
-                                if maze[ny as usize][nx as usize] == 'g' { *goal_unlocked = true; }
-                            }
-                        }
-                    } else {
-                        break;
-                    }
-                },
-                '+' | '-' | '|' => {
-                    break;
-                },
-                _ => break,
-            }
-        }
-
-    let is_running = (running || running2) || (speed > BASE_SPEED + 1e-3);
+    let is_running = running || (speed > BASE_SPEED + 1e-3);
     if is_running && total_dist > 0.0 {
-            let step_interval = 0.18f32;
-            let step_vol = 0.75f32;
             if player.step_timer <= 0.0 {
-                eprintln!("DEBUG: play step sfx: running={} running2={} speed={} total_dist={}", running, running2, speed, total_dist);
-                audio.play_sfx("assets/sfx_step.ogg", step_vol);
-                player.step_timer = step_interval;
+                events.push(GameEvent::Footstep);
+                player.step_timer = STEP_INTERVAL;
             }
         }
     }
 
     while player.a > std::f32::consts::PI { player.a -= 2.0 * std::f32::consts::PI; }
     while player.a < -std::f32::consts::PI { player.a += 2.0 * std::f32::consts::PI; }
-
-    msg
 }
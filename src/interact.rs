@@ -0,0 +1,113 @@
+use crate::caster::cast_ray;
+use crate::door::{door_at, open_door_group, Door};
+use crate::inventory::{key_color_name, Item};
+use crate::events::GameEvent;
+use crate::maze::Maze;
+use crate::pickup::Pickup;
+use crate::player::Player;
+
+pub const REACH_BLOCKS: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Target {
+    Door((usize, usize)),
+    Pickup(usize),
+}
+
+pub fn find_target(
+    maze: &Maze,
+    doors: &[Door],
+    pickups: &[Pickup],
+    player: &Player,
+    block_size: usize,
+) -> Option<Target> {
+    let reach = REACH_BLOCKS * block_size as f32;
+    let inter = cast_ray(maze, doors, player, player.a, block_size);
+
+    let mut best: Option<(f32, Target)> = None;
+    if inter.impact == 'D' && inter.distance <= reach {
+        best = Some((inter.distance, Target::Door((inter.cell_i, inter.cell_j))));
+    }
+
+    let (dir_x, dir_y) = (player.a.cos(), player.a.sin());
+    for (i, p) in pickups.iter().enumerate() {
+        if p.taken { continue; }
+        let px = (p.cell.0 * block_size) as f32 + (block_size as f32) / 2.0;
+        let py = (p.cell.1 * block_size) as f32 + (block_size as f32) / 2.0;
+        let dx = px - player.pos.x;
+        let dy = py - player.pos.y;
+        let dist = (dx*dx + dy*dy).sqrt();
+        let along = dx * dir_x + dy * dir_y;
+        let side = (dx * dir_y - dy * dir_x).abs();
+        let underfoot = dist < (block_size as f32) * 0.5;
+        let in_front = along > 0.0 && along <= reach && side <= (block_size as f32) * 0.45 && along < inter.distance;
+        if !(underfoot || in_front) { continue; }
+        let d = if underfoot { dist } else { along };
+        if best.is_none_or(|(bd, _)| d < bd) {
+            best = Some((d, Target::Pickup(i)));
+        }
+    }
+
+    best.map(|(_, t)| t)
+}
+
+pub fn prompt(target: Target, doors: &[Door], pickups: &[Pickup], player: &Player, use_key: &str) -> Option<String> {
+    match target {
+        Target::Door(cell) => {
+            let door = door_at(doors, cell)?;
+            if door.unlocked {
+                Some(format!("{}: abrir puerta", use_key))
+            } else if player.inventory.has_key(door.lock) {
+                Some(format!("{}: usar llave {}", use_key, key_color_name(door.lock)))
+            } else {
+                Some(format!("Puerta cerrada: necesitas la llave {}", key_color_name(door.lock)))
+            }
+        }
+        Target::Pickup(i) => {
+            let p = pickups.get(i)?;
+            Some(format!("{}: recoger {}", use_key, p.item.label()))
+        }
+    }
+}
+
+pub fn interact(
+    target: Target,
+    maze: &Maze,
+    doors: &mut [Door],
+    pickups: &mut [Pickup],
+    player: &mut Player,
+    goal_unlocked: &mut bool,
+) -> Option<GameEvent> {
+    match target {
+        Target::Door(cell) => {
+            let door = door_at(doors, cell)?;
+            let lock = door.lock;
+            if door.unlocked {
+                open_door_group(doors, cell);
+                return None;
+            }
+            if !player.inventory.remove_one(Item::Key(lock)) {
+                return Some(GameEvent::DoorLocked(lock));
+            }
+            let opened = open_door_group(doors, cell);
+            let neigh = [(1isize,0isize),(-1,0),(0,1),(0,-1)];
+            for &(ox, oy) in opened.iter() {
+                for &(dx, dy) in neigh.iter() {
+                    let nx = ox as isize + dx;
+                    let ny = oy as isize + dy;
+                    if ny >= 0 && nx >= 0 && (ny as usize) < maze.len() && (nx as usize) < maze[0].len() && maze[ny as usize][nx as usize] == 'g' {
+                        *goal_unlocked = true;
+                    }
+                }
+            }
+            Some(GameEvent::DoorOpened(lock))
+        }
+        Target::Pickup(i) => {
+            let p = pickups.get_mut(i)?;
+            if p.taken { return None; }
+            p.taken = true;
+            player.pickup_item(p.item);
+            Some(GameEvent::PickedUp(p.item))
+        }
+    }
+}
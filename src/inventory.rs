@@ -0,0 +1,105 @@
+use raylib::color::Color;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Item {
+    Key(u8),
+    Medkit,
+    Binoculars,
+}
+
+impl Item {
+    pub fn label(&self) -> String {
+        match self {
+            Item::Key(id) => format!("Llave {}", key_color_name(*id)),
+            Item::Medkit => "Medkit".to_string(),
+            Item::Binoculars => "Binoculares".to_string(),
+        }
+    }
+}
+
+pub fn key_color_name(id: u8) -> &'static str {
+    match id {
+        0 => "dorada",
+        1 => "roja",
+        2 => "azul",
+        3 => "verde",
+        _ => "extra",
+    }
+}
+
+pub fn key_color(id: u8) -> Color {
+    match id {
+        0 => Color::GOLD,
+        1 => Color::RED,
+        2 => Color::BLUE,
+        3 => Color::GREEN,
+        _ => Color::LIGHTGRAY,
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ItemStack {
+    pub item: Item,
+    pub count: u32,
+}
+
+#[derive(Default)]
+pub struct Inventory {
+    slots: Vec<ItemStack>,
+    active: usize,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Inventory { slots: Vec::new(), active: 0 }
+    }
+
+    pub fn slots(&self) -> &[ItemStack] { &self.slots }
+
+    pub fn active_index(&self) -> usize { self.active }
+
+    pub fn set_active(&mut self, index: usize) {
+        self.active = index.min(self.slots.len().saturating_sub(1));
+    }
+
+    pub fn active_item(&self) -> Option<Item> {
+        self.slots.get(self.active).map(|s| s.item)
+    }
+
+    pub fn add(&mut self, item: Item) {
+        self.add_many(item, 1);
+    }
+
+    pub fn add_many(&mut self, item: Item, count: u32) {
+        if count == 0 { return; }
+        if let Some(slot) = self.slots.iter_mut().find(|s| s.item == item) {
+            slot.count += count;
+        } else {
+            self.slots.push(ItemStack { item, count });
+        }
+    }
+
+    pub fn count(&self, item: Item) -> u32 {
+        self.slots.iter().find(|s| s.item == item).map(|s| s.count).unwrap_or(0)
+    }
+
+    pub fn has_key(&self, id: u8) -> bool { self.count(Item::Key(id)) > 0 }
+
+    pub fn remove_one(&mut self, item: Item) -> bool {
+        let Some(idx) = self.slots.iter().position(|s| s.item == item) else { return false; };
+        self.slots[idx].count -= 1;
+        if self.slots[idx].count == 0 {
+            self.slots.remove(idx);
+            if self.active > idx || self.active >= self.slots.len() {
+                self.active = self.active.saturating_sub(1);
+            }
+        }
+        true
+    }
+
+    pub fn cycle(&mut self, dir: i32) {
+        if self.slots.is_empty() { self.active = 0; return; }
+        let n = self.slots.len() as i32;
+        self.active = ((self.active as i32 + dir).rem_euclid(n)) as usize;
+    }
+}
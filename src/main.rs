@@ -1,7 +1,5 @@
 use raylib::prelude::*;
-use raylib::consts::KeyboardKey;
 use std::time::Instant;
-use std::f32::consts::PI;
 
 mod framebuffer;
 mod line;
@@ -12,67 +10,247 @@ mod generator;
 mod input;
 mod enemy;
 mod textures;
+mod assets;
+mod hotreload;
 mod audio;
-
-use framebuffer::Framebuffer;
-use maze::Maze;
+mod audio_backend;
+mod inventory;
+mod door;
+mod pickup;
+mod interact;
+mod collision;
+mod actions;
+mod replay;
+mod world;
+mod events;
+mod messages;
+mod save;
+mod settings;
+mod spatial;
+mod music;
+
+use framebuffer::{Framebuffer, Hud, SpriteDraw};
+use maze::{blocks_view, is_see_through, wall_height, Maze};
+use collision::is_blocked;
 use player::Player;
-use caster::cast_ray;
-use input::process_events;
-use generator::generate_maze_text;
+use caster::cast_ray_layers;
+use input::{Controls, InputFrame};
+use replay::Replay;
+use rand::Rng;
+use world::GameWorld;
+use events::{EventBus, GameEvent, Stats};
+use messages::Messages;
+use save::{delete_save, load_world, save_world};
+use settings::{Settings, RESOLUTIONS};
+use actions::{Action, Keymap};
 use enemy::Enemy;
-use textures::TextureManager;
-use audio::AudioManager;
+use textures::{asset_texture, sprite_texture, TextureManager};
+use assets::{AssetKind, AssetLoader};
+use hotreload::AssetWatcher;
+use audio::{AudioManager, Bus, SfxSubscriber};
+use music::MusicState;
+use inventory::{key_color, Item};
+use door::{Door, GOAL_LOCK};
+use pickup::Pickup;
+use interact::prompt;
+
+
+#[derive(Clone, Copy, PartialEq)]
+enum MenuEntry {
+    Continue,
+    NewGame,
+    Options,
+    Controls,
+    Quit,
+}
+
+impl MenuEntry {
+    fn label(self) -> &'static str {
+        match self {
+            MenuEntry::Continue => "Continuar",
+            MenuEntry::NewGame => "Empezar a jugar",
+            MenuEntry::Options => "Opciones",
+            MenuEntry::Controls => "Controles",
+            MenuEntry::Quit => "Salir",
+        }
+    }
+}
+
+fn menu_entries(has_save: bool) -> Vec<MenuEntry> {
+    let mut entries = Vec::new();
+    if has_save { entries.push(MenuEntry::Continue); }
+    entries.extend([MenuEntry::NewGame, MenuEntry::Options, MenuEntry::Controls, MenuEntry::Quit]);
+    entries
+}
 
-struct Medkit { cell: (usize, usize), taken: bool }
-struct PickupKey { cell: (usize, usize), taken: bool }
-struct PickupBinocular { cell: (usize, usize), taken: bool }
+const PAUSE_OPTIONS: [&str; 6] = ["Reanudar", "Reiniciar (misma semilla)", "Guardar partida", "Cargar partida", "Ajustes", "Salir al menu"];
+const PAUSE_DUCK: f32 = 0.3;
 
 enum AppState {
     Menu { selected: usize },
-    SoundMenu { volume: f32, previous_selected: usize },
+    Paused { selected: usize },
+    OptionsMenu { row: usize, previous_selected: usize, from_pause: bool },
+    ControlsMenu { previous_selected: usize, row: usize, listening: bool },
     Playing,
     Victory,
     GameOver,
     Exiting,
 }
 
-fn draw_menu(window: &mut RaylibHandle, raylib_thread: &RaylibThread, selected: usize) {
+#[derive(Clone, Copy, PartialEq)]
+enum Screen {
+    Menu,
+    Paused,
+    Settings,
+    Playing,
+    Ended,
+    Exiting,
+}
+
+impl AppState {
+    fn screen(&self) -> Screen {
+        match self {
+            AppState::Menu { .. } => Screen::Menu,
+            AppState::Paused { .. } => Screen::Paused,
+            AppState::OptionsMenu { from_pause: true, .. } => Screen::Paused,
+            AppState::OptionsMenu { .. } | AppState::ControlsMenu { .. } => Screen::Settings,
+            AppState::Playing => Screen::Playing,
+            AppState::Victory | AppState::GameOver => Screen::Ended,
+            AppState::Exiting => Screen::Exiting,
+        }
+    }
+}
+
+fn draw_menu(window: &mut RaylibHandle, raylib_thread: &RaylibThread, entries: &[MenuEntry], selected: usize, notice: Option<&str>) {
     let mut d = window.begin_drawing(raylib_thread);
     d.clear_background(Color::RAYWHITE);
     d.draw_text("Proyecto 1 - Mansion embrujada - Escapa de los Mimikyus -Ihan Marroquin", 60, 40, 44, Color::DARKGRAY);
-    let options = ["Empezar a jugar", "Sonido", "Salir"];
     let mut y = 160;
-    for (i,&opt) in options.iter().enumerate() {
+    for (i,entry) in entries.iter().enumerate() {
+        let color = if i==selected { Color::RED } else { Color::BLACK };
+        d.draw_text(entry.label(), 120, y, 30, color);
+        y += 60;
+    }
+    d.draw_text("Usa ARRIBA/ABAJO para navegar, ENTER seleccionar", 60, y + 20, 20, Color::DARKGRAY);
+    if let Some(text) = notice { d.draw_text(text, 60, y + 60, 22, Color::MAROON); }
+}
+
+fn draw_pause_menu(window: &mut RaylibHandle, raylib_thread: &RaylibThread, selected: usize, notice: Option<&str>) {
+    let mut d = window.begin_drawing(raylib_thread);
+    d.clear_background(Color::RAYWHITE);
+    d.draw_text("Pausa", 60, 40, 44, Color::DARKGRAY);
+    let mut y = 160;
+    for (i,&opt) in PAUSE_OPTIONS.iter().enumerate() {
         let color = if i==selected { Color::RED } else { Color::BLACK };
         d.draw_text(opt, 120, y, 30, color);
         y += 60;
     }
-    d.draw_text("Usa ARRIBA/ABAJO para navegar, ENTER seleccionar", 60, 420, 20, Color::DARKGRAY);
+    if let Some(text) = notice { d.draw_text(text, 60, y + 20, 22, Color::MAROON); }
+}
+
+fn draw_loading(window: &mut RaylibHandle, raylib_thread: &RaylibThread, progress: f32, current: &str) {
+    let (w, h) = (window.get_screen_width(), window.get_screen_height());
+    let mut d = window.begin_drawing(raylib_thread);
+    d.clear_background(Color::BLACK);
+    d.draw_text("Cargando recursos...", 60, h / 2 - 60, 36, Color::RAYWHITE);
+    d.draw_rectangle_lines(60, h / 2, w - 120, 30, Color::RAYWHITE);
+    d.draw_rectangle(64, h / 2 + 4, ((w - 128) as f32 * progress) as i32, 22, Color::MAROON);
+    d.draw_text(current, 60, h / 2 + 50, 20, Color::GRAY);
 }
 
-fn draw_sound_menu(window: &mut RaylibHandle, raylib_thread: &RaylibThread, volume: f32) {
+fn set_cursor_captured(window: &mut RaylibHandle, controls: &mut Controls, captured: bool) {
+    if captured { window.disable_cursor(); } else { window.enable_cursor(); }
+    controls.look.captured = captured;
+}
+
+const OPTION_ROWS: usize = 12;
+
+fn draw_options_menu(window: &mut RaylibHandle, raylib_thread: &RaylibThread, settings: &Settings, row: usize) {
     let mut d = window.begin_drawing(raylib_thread);
     d.clear_background(Color::RAYWHITE);
-    d.draw_text("Ajustes de Sonido", 60, 40, 44, Color::DARKGRAY);
-    d.draw_text("Volumen:", 120, 180, 28, Color::BLACK);
-
-    let x = 120;
-    let y = 220;
-    let w = 600;
-    let h = 24;
-    d.draw_rectangle(x, y, w, h, Color::LIGHTGRAY);
-    let fill = ((volume.clamp(0.0,1.0) * w as f32).round() as i32).max(0);
-    d.draw_rectangle(x, y, fill, h, Color::GREEN);
-    d.draw_rectangle_lines(x, y, w, h, Color::DARKGRAY);
-
-    let pct_text = format!("{:.0}%", (volume.clamp(0.0,1.0) * 100.0));
-    d.draw_text(&pct_text, x + w + 16, y, 22, Color::DARKGRAY);
-
-    d.draw_text("Izquierda/Derecha para ajustar - ENTER para volver", 120, 280, 20, Color::DARKGRAY);
+    d.draw_text("Opciones", 60, 40, 44, Color::DARKGRAY);
+
+    let mut rows: Vec<(&str, String, Option<f32>)> = Bus::ALL
+        .iter()
+        .map(|&bus| (bus.label(), format!("{:.0}%", settings.volume(bus) * 100.0), Some(settings.volume(bus))))
+        .collect();
+    rows.extend([
+        ("Silenciar", (if settings.muted { "Si" } else { "No" }).to_string(), None),
+        ("Resolucion", format!("{}x{}", settings.window_w, settings.window_h), None),
+        ("Campo de vision", format!("{:.0} grados", settings.fov_deg), None),
+        ("Escala de render", format!("{}", settings.render_scale), None),
+        ("Filtro de texturas", settings.texture_filter.label().to_string(), None),
+        ("Sensibilidad del raton", format!("{:.1}", settings.mouse_sensitivity * 1000.0), None),
+        ("Invertir eje Y", (if settings.invert_mouse { "Si" } else { "No" }).to_string(), None),
+        ("Volver", String::new(), None),
+    ]);
+    let mut y = 120;
+    for (i, (label, value, slider)) in rows.iter().enumerate() {
+        let color = if i == row { Color::RED } else { Color::BLACK };
+        d.draw_text(label, 120, y, 28, color);
+        if let Some(volume) = slider {
+            let (x, w, h) = (480, 400, 24);
+            let bar = if settings.muted { Color::GRAY } else { Color::GREEN };
+            d.draw_rectangle(x, y, w, h, Color::LIGHTGRAY);
+            d.draw_rectangle(x, y, (volume * w as f32).round() as i32, h, bar);
+            d.draw_rectangle_lines(x, y, w, h, Color::DARKGRAY);
+            d.draw_text(value, x + w + 16, y, 22, Color::DARKGRAY);
+        } else {
+            d.draw_text(value, 480, y, 28, color);
+        }
+        y += 46;
+    }
+
+    d.draw_text("ARRIBA/ABAJO navegar - Izquierda/Derecha ajustar - ENTER/RETROCESO volver", 120, y + 20, 20, Color::DARKGRAY);
 }
 
-fn draw_victory(window: &mut RaylibHandle, raylib_thread: &RaylibThread, win_tex: Option<&Texture2D>) {
+fn apply_audio_settings(audio: &mut AudioManager, settings: &Settings) {
+    for bus in Bus::ALL {
+        audio.set_bus_volume(bus, settings.volume(bus));
+    }
+    audio.set_muted(settings.muted);
+}
+
+fn controls_rows() -> Vec<Action> {
+    Action::ALL.iter().copied().filter(|a| a.rebindable()).collect()
+}
+
+fn draw_controls_menu(window: &mut RaylibHandle, raylib_thread: &RaylibThread, controls: &Controls, row: usize, listening: bool) {
+    let mut d = window.begin_drawing(raylib_thread);
+    d.clear_background(Color::RAYWHITE);
+    d.draw_text("Controles", 60, 40, 44, Color::DARKGRAY);
+    d.draw_text("Teclado", 520, 80, 20, Color::DARKGRAY);
+    d.draw_text("Mando", 900, 80, 20, Color::DARKGRAY);
+
+    let actions = controls_rows();
+    let rows = (actions.len() + 2) as i32;
+    let step = ((d.get_screen_height() - 110 - 16 - 60) / rows).clamp(16, 34);
+    let font = (step - 8).min(24);
+    let mut y = 110;
+    for (i, action) in actions.iter().enumerate() {
+        let color = if i == row { Color::RED } else { Color::BLACK };
+        d.draw_text(action.label(), 120, y, font, color);
+        let keys = if i == row && listening { "Pulsa una tecla...".to_string() } else { controls.keymap.describe(*action) };
+        d.draw_text(&keys, 520, y, font, color);
+        d.draw_text(&controls.keymap.describe_pad(*action), 900, y, font, Color::DARKGRAY);
+        y += step;
+    }
+
+    let extra = [
+        "Restaurar teclas por defecto".to_string(),
+        "Volver".to_string(),
+    ];
+    y += 16;
+    for (k, text) in extra.iter().enumerate() {
+        let color = if actions.len() + k == row { Color::RED } else { Color::BLACK };
+        d.draw_text(text, 120, y, font, color);
+        y += step;
+    }
+
+    d.draw_text("ARRIBA/ABAJO navegar - ENTER cambiar tecla - RETROCESO volver", 120, y + 10, 20, Color::DARKGRAY);
+}
+
+fn draw_victory(window: &mut RaylibHandle, raylib_thread: &RaylibThread, win_tex: Option<&Texture2D>, stats: &Stats) {
     let mut d = window.begin_drawing(raylib_thread);
     d.clear_background(Color::RAYWHITE);
     if let Some(tex) = win_tex {
@@ -90,9 +268,10 @@ fn draw_victory(window: &mut RaylibHandle, raylib_thread: &RaylibThread, win_tex
         d.draw_text("GANASTE! Escapaste de la casa embrujada", 80, 200, 36, Color::GREEN);
         d.draw_text("Presiona ENTER para volver al menu", 80, 300, 22, Color::DARKGRAY);
     }
+    d.draw_text(&stats.summary(), 80, d.get_screen_height() - 60, 24, Color::DARKGRAY);
 }
 
-fn draw_gameover(window: &mut RaylibHandle, raylib_thread: &RaylibThread, game_over_tex: Option<&Texture2D>) {
+fn draw_gameover(window: &mut RaylibHandle, raylib_thread: &RaylibThread, game_over_tex: Option<&Texture2D>, stats: &Stats) {
     let mut d = window.begin_drawing(raylib_thread);
     d.clear_background(Color::RAYWHITE);
 
@@ -112,59 +291,106 @@ fn draw_gameover(window: &mut RaylibHandle, raylib_thread: &RaylibThread, game_o
         d.draw_text("GAME OVER", 220, 200, 64, Color::RED);
         d.draw_text("Has muerto. Presiona ENTER para volver al menu.", 110, 300, 22, Color::DARKGRAY);
     }
+    d.draw_text(&stats.summary(), 80, d.get_screen_height() - 60, 24, Color::DARKGRAY);
 }
 
 
-fn expand_maze(maze: &Maze, factor: usize) -> Maze {
-    let rows = maze.len();
-    let cols = maze[0].len();
-    let mut out: Maze = Vec::with_capacity(rows * factor);
-    for r in 0..rows {
-        let mut new_rows: Vec<Vec<char>> = vec![Vec::with_capacity(cols * factor); factor];
-        for c in 0..cols {
-            let ch = maze[r][c];
-            for fr in 0..factor {
-                for _fc in 0..factor {
-                    new_rows[fr].push(ch);
-                }
-            }
+fn backdrop(y: f32, horizon: f32) -> Color {
+    let sky_top = Color::new(40, 10, 60, 255);
+    let sky_bottom = Color::new(140, 50, 160, 255);
+    let floor_col = Color::new(90, 45, 20, 255);
+    if y >= horizon { return floor_col; }
+    let t = y / horizon;
+    let r = ((1.0 - t) * (sky_top.r as f32) + t * (sky_bottom.r as f32)) as u8;
+    let g = ((1.0 - t) * (sky_top.g as f32) + t * (sky_bottom.g as f32)) as u8;
+    let b = ((1.0 - t) * (sky_top.b as f32) + t * (sky_bottom.b as f32)) as u8;
+    Color::new(r, g, b, 255)
+}
+
+fn blend(under: Color, over: Color) -> Color {
+    let a = over.a as u16;
+    let mix = |u: u8, o: u8| ((o as u16 * a + u as u16 * (255 - a)) / 255) as u8;
+    Color::new(mix(under.r, over.r), mix(under.g, over.g), mix(under.b, over.b), 255)
+}
+
+struct WallSpan {
+    top_f: f32,
+    unit: f32,
+    height: f32,
+    tex_char: char,
+    u: f32,
+    tint: Option<Color>,
+    opaque: bool,
+}
+
+impl WallSpan {
+    fn sample(&self, texmgr: &TextureManager, v: f32) -> Color {
+        let (tex_w, tex_h) = texmgr.tex_size(self.tex_char).unwrap_or((1usize,1usize));
+        if tex_w == 1 || tex_h == 1 { return Color::GRAY; }
+        let v = if self.height <= 1.0 { v + 1.0 - self.height } else { v };
+        let mut c = texmgr.sample_char(self.tex_char, self.u, v.min(self.height.max(1.0) - 1e-4), tex_h as f32 / self.unit);
+        if let Some(t) = self.tint {
+            c.r = ((c.r as u16 * 2 + t.r as u16) / 3) as u8;
+            c.g = ((c.g as u16 * 2 + t.g as u16) / 3) as u8;
+            c.b = ((c.b as u16 * 2 + t.b as u16) / 3) as u8;
+        }
+        if self.opaque { c.a = 255; }
+        c
+    }
+}
+
+const SEE_THROUGH_VEIL: f32 = 0.6;
+
+struct ColumnDepth {
+    wall: f32,
+    layers: Vec<(f32, f32, bool)>,
+}
+
+type SpriteEntry<'a> = (&'a Texture2D, f32, f32, f32, f32, Color);
+
+fn sprite_strips<'a>(sprite: &SpriteEntry<'a>, depths: &[ColumnDepth], render_scale: usize) -> Vec<SpriteDraw<'a>> {
+    let &(tex, scale, x, y, dist, tint) = sprite;
+    let (w, h) = (tex.width() as f32 * scale, tex.height() as f32 * scale);
+    let rs = render_scale as f32;
+    let first = (x / rs).floor().max(0.0) as usize;
+    let last = (((x + w) / rs).ceil().max(0.0) as usize).min(depths.len());
+    let mut strips = Vec::new();
+    for (col, depth) in depths.iter().enumerate().take(last).skip(first) {
+        if dist >= depth.wall { continue; }
+        let x0 = (col as f32 * rs).max(x);
+        let x1 = ((col + 1) as f32 * rs).min(x + w);
+        let mut bottom = y + h;
+        let mut alpha = tint.a as f32;
+        for &(_, top, see_through) in depth.layers.iter().filter(|l| l.0 < dist) {
+            if see_through { alpha *= SEE_THROUGH_VEIL; } else { bottom = bottom.min(top); }
         }
-        for nr in new_rows.into_iter() { out.push(nr); }
+        if x1 <= x0 || bottom <= y { continue; }
+        let src = Rectangle::new((x0 - x) / scale, 0.0, (x1 - x0) / scale, (bottom - y) / scale);
+        let dest = Rectangle::new(x0, y, x1 - x0, bottom - y);
+        strips.push((tex, src, dest, Color::new(tint.r, tint.g, tint.b, alpha as u8)));
     }
-    out
+    strips
 }
 
 fn render_world_textured(
     framebuffer: &mut Framebuffer,
     maze: &Maze,
+    doors: &[Door],
     player: &Player,
     block_size: usize,
     render_scale: usize,
     texmgr: &TextureManager,
-) -> Vec<f32> {
+) -> Vec<ColumnDepth> {
     let screen_w_px = framebuffer.width() as usize;
     let screen_h = framebuffer.height() as usize;
-    let hh = framebuffer.height() as f32 / 2.0;
+    let hh = player.horizon(screen_h as f32);
+    let eye = player.eye_height();
     let num_cols = (screen_w_px / render_scale).max(1);
     let proj_plane_dist = (num_cols as f32 / 2.0) / (player.fov / 2.0).tan();
-    let mut wall_distances = vec![f32::INFINITY; num_cols];
+    let mut depths = Vec::with_capacity(num_cols);
 
-    let sky_top = Color::new(40, 10, 60, 255);
-    let sky_bottom = Color::new(140, 50, 160, 255);
-    let floor_col = Color::new(90, 45, 20, 255);
-
-    for y in 0..(screen_h/2) {
-        let t = y as f32 / (screen_h as f32 / 2.0);
-        let r = ((1.0 - t) * (sky_top.r as f32) + t * (sky_bottom.r as f32)) as u8;
-        let g = ((1.0 - t) * (sky_top.g as f32) + t * (sky_bottom.g as f32)) as u8;
-        let b = ((1.0 - t) * (sky_top.b as f32) + t * (sky_bottom.b as f32)) as u8;
-        framebuffer.set_current_color(Color::new(r,g,b,255));
-        for x in 0..screen_w_px {
-            if (x as u32) < framebuffer.width() { framebuffer.set_pixel(x as u32, y as u32); }
-        }
-    }
-    for y in (screen_h/2)..screen_h {
-        framebuffer.set_current_color(floor_col);
+    for y in 0..screen_h {
+        framebuffer.set_current_color(backdrop(y as f32, hh));
         for x in 0..screen_w_px {
             if (x as u32) < framebuffer.width() { framebuffer.set_pixel(x as u32, y as u32); }
         }
@@ -173,57 +399,48 @@ fn render_world_textured(
     for col in 0..num_cols {
         let current = if num_cols == 1 { 0.5 } else { col as f32 / (num_cols - 1) as f32 };
         let angle = player.a - (player.fov/2.0) + (player.fov * current);
-        let inter = cast_ray(framebuffer, maze, player, angle, block_size, false);
+        let hits = cast_ray_layers(maze, doors, player, angle, block_size);
         let delta = angle - player.a;
-        let corrected_dist = inter.distance * delta.cos().abs().max(1e-6);
-        wall_distances[col] = corrected_dist;
-
-        if corrected_dist <= 0.0 || !corrected_dist.is_finite() { continue; }
-
-        let wall_h = block_size as f32;
-        let stake_h = (wall_h / corrected_dist) * proj_plane_dist;
-        if !stake_h.is_finite() || stake_h <= 0.0 { continue; }
-
-        let top_f = hh - (stake_h / 2.0);
-        let bottom_f = hh + (stake_h / 2.0);
-        let top = top_f.max(0.0) as isize;
-        let bottom = bottom_f.min(screen_h as f32) as isize;
-
-        let local_x = inter.hit_x - (inter.cell_i as f32 * block_size as f32);
-        let local_y = inter.hit_y - (inter.cell_j as f32 * block_size as f32);
-
-        let eps = 1.0;
-        let frac = if local_x < eps {
-            (local_y / block_size as f32).fract()
-        } else if local_x > (block_size as f32 - eps) {
-            (local_y / block_size as f32).fract()
-        } else if local_y < eps {
-            (local_x / block_size as f32).fract()
-        } else if local_y > (block_size as f32 - eps) {
-            (local_x / block_size as f32).fract()
-        } else {
-            (local_x / block_size as f32).fract()
-        };
-
-        let mut tex_char = inter.impact;
-        if tex_char == 'p' { tex_char = ' '; } 
-
-        let (tex_w, tex_h) = texmgr.tex_size(tex_char).unwrap_or((1usize,1usize));
-        let tx_index = if tex_w > 1 { ((frac * ((tex_w - 1) as f32)).round() as usize).min(tex_w - 1) } else { 0usize };
+        let cos = delta.cos().abs().max(1e-6);
+        let wall = hits.iter().find(|h| blocks_view(h.impact)).map_or(f32::INFINITY, |w| w.distance * cos);
+        let layers = hits
+            .iter()
+            .map(|h| (h.distance * cos, h.impact))
+            .filter(|&(d, ch)| !blocks_view(ch) && d > 0.0 && d < wall)
+            .map(|(d, ch)| (d, hh + (eye - wall_height(ch)) * (block_size as f32 / d) * proj_plane_dist, is_see_through(ch)))
+            .collect();
+        depths.push(ColumnDepth { wall, layers });
+
+        let spans: Vec<WallSpan> = hits
+            .iter()
+            .rev()
+            .filter_map(|inter| {
+                let corrected_dist = inter.distance * cos;
+                if corrected_dist <= 0.0 || !corrected_dist.is_finite() { return None; }
+                let unit = (block_size as f32 / corrected_dist) * proj_plane_dist;
+                if !unit.is_finite() || unit <= 0.0 { return None; }
+                let tex_char = if inter.impact == 'p' { ' ' } else { inter.impact };
+                let tint = if tex_char == 'D' {
+                    door::lock_at(doors, (inter.cell_i, inter.cell_j)).filter(|&l| l != GOAL_LOCK).map(key_color)
+                } else {
+                    None
+                };
+                let height = wall_height(tex_char);
+                Some(WallSpan { top_f: hh + (eye - height) * unit, unit, height, tex_char, u: inter.tex_u, tint, opaque: !is_see_through(tex_char) })
+            })
+            .collect();
+        let top = spans.iter().map(|s| s.top_f).fold(f32::INFINITY, f32::min).max(0.0) as isize;
+        let bottom = spans.iter().map(|s| s.top_f + s.height * s.unit).fold(0.0, f32::max).min(screen_h as f32) as isize;
 
         let x_px_start = (col * render_scale) as u32;
         for sy in top..bottom {
-            let y_rel = (sy as f32 - top_f) / stake_h; 
-            let color = if tex_w == 1 || tex_h == 1 {
-                Color::GRAY
-            } else {
-                let ty_i = ((y_rel * ((tex_h - 1) as f32)).round() as usize).min(tex_h - 1);
-                let u = (tx_index as f32) / (tex_w as f32 - 1.0).max(1.0);
-                let v = (ty_i as f32) / (tex_h as f32 - 1.0).max(1.0);
-                let mut c = texmgr.sample_char(tex_char, u, v);
-                c.a = 255;
-                c
-            };
+            let mut color = backdrop(sy as f32, hh);
+            for span in spans.iter() {
+                let v = (sy as f32 - span.top_f) / span.unit;
+                if !(0.0..span.height).contains(&v) { continue; }
+                let c = span.sample(texmgr, v);
+                color = if span.opaque { c } else { blend(color, c) };
+            }
 
             framebuffer.set_current_color(color);
             for dx in 0..render_scale {
@@ -235,7 +452,7 @@ fn render_world_textured(
         }
     }
 
-    wall_distances
+    depths
 }
 
 fn draw_minimap_with_fog(
@@ -244,9 +461,8 @@ fn draw_minimap_with_fog(
     fog: &Vec<Vec<bool>>,
     player: &Player,
     enemies: &Vec<Enemy>,
-    medkits: &Vec<Medkit>,
-    keys: &Vec<PickupKey>,
-    bins: &Vec<PickupBinocular>,
+    pickups: &[Pickup],
+    doors: &[Door],
     map_w: usize,
     map_h: usize,
     offset_x: usize,
@@ -280,8 +496,14 @@ fn draw_minimap_with_fog(
                 match cell {
                     '+'|'-'|'|' => Color::DARKGRAY,
                     'p' => Color::RED,
-                    'D' => Color::new(150,75,0,255),
+                    'D' => match door::door_at(doors, (i, j)) {
+                        Some(d) if d.is_passable() => Color::WHITE,
+                        Some(d) if d.lock != GOAL_LOCK => key_color(d.lock),
+                        _ => Color::new(150,75,0,255),
+                    },
                     'g' => Color::GREEN,
+                    c if is_see_through(c) => Color::new(120, 150, 180, 255),
+                    _ if is_blocked(maze, doors, i as isize, j as isize) => Color::DARKGRAY,
                     _ => Color::WHITE,
                 }
             };
@@ -300,13 +522,13 @@ fn draw_minimap_with_fog(
         }
     }
 
-    for m in medkits.iter() {
-        if m.taken { continue; }
-        let show = fog[m.cell.1][m.cell.0] || player.binocular_timer > 0.0;
+    for p in pickups.iter() {
+        if p.taken { continue; }
+        let show = p.item != Item::Medkit || fog[p.cell.1][p.cell.0] || player.binocular_timer > 0.0;
         if show {
-            let xo = offset_x + m.cell.0 * block;
-            let yo = offset_y + m.cell.1 * block;
-            framebuffer.set_current_color(Color::SKYBLUE);
+            let xo = offset_x + p.cell.0 * block;
+            let yo = offset_y + p.cell.1 * block;
+            framebuffer.set_current_color(p.minimap_color());
             let cx = (xo + block/2) as i32;
             let cy = (yo + block/2) as i32;
             for oy in -1..=1 { for ox in -1..=1 {
@@ -319,24 +541,6 @@ fn draw_minimap_with_fog(
         }
     }
 
-    for b in bins.iter() { if b.taken { continue; } let xo = offset_x + b.cell.0 * block; let yo = offset_y + b.cell.1 * block; framebuffer.set_current_color(Color::PURPLE); let cx = (xo + block/2) as i32; let cy = (yo + block/2) as i32; for oy in -1..=1 { for ox in -1..=1 { let sx = cx + ox; let sy = cy + oy; if sx >= 0 && sy >= 0 { let su = sx as u32; let sv = sy as u32; if su < framebuffer.width() && sv < framebuffer.height() { framebuffer.set_pixel(su, sv); } } } } }
-
-    for k in keys.iter() {
-        if k.taken { continue; }
-        let xo = offset_x + k.cell.0 * block;
-        let yo = offset_y + k.cell.1 * block;
-        framebuffer.set_current_color(Color::GOLD);
-        let cx = (xo + block/2) as i32;
-        let cy = (yo + block/2) as i32;
-        for oy in -1..=1 { for ox in -1..=1 {
-            let sx = cx + ox; let sy = cy + oy;
-            if sx >= 0 && sy >= 0 {
-                let su = sx as u32; let sv = sy as u32;
-                if su < framebuffer.width() && sv < framebuffer.height() { framebuffer.set_pixel(su, sv); }
-            }
-        } }
-    }
-
     for j in 0..rows {
         for i in 0..cols {
             if maze[j][i] == 'g' {
@@ -397,58 +601,71 @@ fn draw_minimap_with_fog(
 }
 
 fn main() {
-    const WINDOW_W: i32 = 1820;
-    const WINDOW_H: i32 = 980;
-    const RENDER_SCALE: usize = 3;
     const FIXED_DT: f32 = 1.0/60.0;
 
-    let (mut window, raylib_thread) = raylib::init().size(WINDOW_W, WINDOW_H).title("Ihan Marroquin - 23108").build();
-
-    let mimikyu_tex = Image::load_image("assets/mimikyu_1.png")
-        .and_then(|img| window.load_texture_from_image(&raylib_thread, &img))
-        .expect("assets/mimikyu_1.png missing");
-    let medkit_tex = Image::load_image("assets/medkit.png")
-        .and_then(|img| window.load_texture_from_image(&raylib_thread, &img))
-        .expect("assets/medkit.png missing");
-    let key_tex = Image::load_image("assets/key.png")
-        .and_then(|img| window.load_texture_from_image(&raylib_thread, &img))
-        .expect("assets/key.png missing");
-    let binocular_tex = Image::load_image("assets/binoculars.png")
-        .and_then(|img| window.load_texture_from_image(&raylib_thread, &img))
-        .expect("assets/binoculars.png missing");
-    let door_tex = Image::load_image("assets/door.png")
-        .and_then(|img| window.load_texture_from_image(&raylib_thread, &img))
-        .expect("assets/door.png missing");
-    let game_over_tex: Option<Texture2D> = Image::load_image("assets/game_over.png")
-        .ok()
-        .and_then(|img| window.load_texture_from_image(&raylib_thread, &img).ok());
-    let win_tex: Option<Texture2D> = Image::load_image("assets/win.png")
-        .ok()
-        .and_then(|img| window.load_texture_from_image(&raylib_thread, &img).ok());
-
-
-    let texmgr = TextureManager::new(&mut window, &raylib_thread);
-
-    let mut music_volume = 0.45_f32;
-    let mut audio = AudioManager::new_loop("assets/music.ogg", music_volume, 0.4);
+    let settings_path = settings::settings_path();
+    let mut settings = Settings::load(&settings_path);
+
+    let (mut window, raylib_thread) = raylib::init().size(settings.window_w, settings.window_h).title("Ihan Marroquin - 23108").build();
+
+    let mut loader = AssetLoader::new(assets::load_manifest());
+    while !loader.done() {
+        if window.window_should_close() { return; }
+        draw_loading(&mut window, &raylib_thread, loader.progress(), loader.current().unwrap_or(""));
+        loader.load_next();
+    }
+    let mut assets = loader.finish();
+
+    let mut mimikyu_tex = sprite_texture(&mut window, &raylib_thread, &assets, "mimikyu");
+    let mut medkit_tex = sprite_texture(&mut window, &raylib_thread, &assets, "medkit");
+    let mut key_tex = sprite_texture(&mut window, &raylib_thread, &assets, "key");
+    let mut binocular_tex = sprite_texture(&mut window, &raylib_thread, &assets, "binoculars");
+    let mut game_over_tex = asset_texture(&mut window, &raylib_thread, &assets, AssetKind::Screen, "game_over");
+    let mut win_tex = asset_texture(&mut window, &raylib_thread, &assets, AssetKind::Screen, "win");
+
+    let mut texmgr = TextureManager::new(&mut window, &raylib_thread, &assets);
+    texmgr.filter = settings.texture_filter;
+
+    let mut audio = AudioManager::new(&assets);
+    apply_audio_settings(&mut audio, &settings);
 
     let mut state = AppState::Menu { selected: 0 };
     let mut last = Instant::now();
     let mut accumulator = 0.0f32;
     let mut fps = 0.0f32;
 
-    let mut maze: Maze = Vec::new();
-    let mut player: Option<Player> = None;
-    let mut block_size: usize = 0usize;
-    let mut enemies: Vec<Enemy> = Vec::new();
-    let mut medkits: Vec<Medkit> = Vec::new();
-    let mut keys: Vec<PickupKey> = Vec::new();
-    let mut bins: Vec<PickupBinocular> = Vec::new();
-    let mut fog: Vec<Vec<bool>> = Vec::new();
-    let mut message: Option<(String,f32)> = None;
-    let mut goal_unlocked: bool = false;
+    let mut world: Option<GameWorld> = None;
+    let mut messages = Messages::default();
+    if let Some(report) = assets.report() {
+        eprintln!("Recursos: {}", report);
+        messages.show(&report, 8.0);
+    }
+    let mut stats = Stats::default();
+    let mut bus = EventBus::default();
+    let mut controls = Controls::load();
+    controls.look.sensitivity = settings.mouse_sensitivity;
+    controls.look.invert = settings.invert_mouse;
+    messages.use_item_key = controls.keymap.describe(Action::UseItem);
+    let mut show_map = true;
+    let mut pending = InputFrame::default();
+    let mut recording: Option<Replay> = None;
+    let mut playback: Option<Replay> = None;
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|a| a == "--replay") {
+        match args.get(i + 1).map(|path| Replay::load(std::path::Path::new(path))) {
+            Some(Ok(r)) => playback = Some(r),
+            Some(Err(e)) => eprintln!("Replay: {}", e),
+            None => eprintln!("Replay: falta la ruta despues de --replay"),
+        }
+    }
+    let mut watcher = args.iter().any(|a| a == "--dev").then(|| AssetWatcher::new(assets.entries()));
+    let mut new_game_seed = playback.as_ref().map(|r| r.seed);
+    let mut has_save = settings::save_path().exists();
+    let mut load_requested = false;
+    let mut from_save = false;
+    let mut screen = state.screen();
 
-    let mut framebuffer = Framebuffer::new(WINDOW_W as u32, WINDOW_H as u32, Color::RAYWHITE);
+    let mut framebuffer = Framebuffer::new(settings.window_w as u32, settings.window_h as u32, Color::RAYWHITE);
 
     'main_loop: loop {
         if window.window_should_close() { break 'main_loop; }
@@ -458,270 +675,302 @@ fn main() {
         last = now;
         if frame_dt > 0.25 { frame_dt = 0.25; }
         fps = if frame_dt > 0.0 { 1.0/frame_dt } else { fps };
-        accumulator += frame_dt;
+        if screen == Screen::Playing { accumulator += frame_dt; }
 
-        if let Some((_, ref mut t)) = message { *t -= frame_dt; if *t <= 0.0 { message = None; } }
+        controls.poll(&window);
+
+        messages.update(frame_dt);
+        audio.update(frame_dt);
+
+        for entry in watcher.as_mut().map(|w| w.poll(frame_dt)).unwrap_or_default() {
+            let asset = match assets.reload(&entry) {
+                Ok(asset) => asset,
+                Err(e) => { messages.show(&format!("No se pudo recargar {}", e), 3.0); continue; }
+            };
+            match (entry.kind, entry.id.as_str()) {
+                (AssetKind::Wall, id) => texmgr.reload(&mut window, &raylib_thread, &assets, id),
+                (AssetKind::Sprite, "mimikyu") => mimikyu_tex = sprite_texture(&mut window, &raylib_thread, &assets, "mimikyu"),
+                (AssetKind::Sprite, "medkit") => medkit_tex = sprite_texture(&mut window, &raylib_thread, &assets, "medkit"),
+                (AssetKind::Sprite, "key") => key_tex = sprite_texture(&mut window, &raylib_thread, &assets, "key"),
+                (AssetKind::Sprite, "binoculars") => binocular_tex = sprite_texture(&mut window, &raylib_thread, &assets, "binoculars"),
+                (AssetKind::Screen, "game_over") => game_over_tex = asset_texture(&mut window, &raylib_thread, &assets, AssetKind::Screen, "game_over"),
+                (AssetKind::Screen, "win") => win_tex = asset_texture(&mut window, &raylib_thread, &assets, AssetKind::Screen, "win"),
+                (AssetKind::Sound | AssetKind::Music, _) => audio.reload_asset(asset),
+                _ => {}
+            }
+            messages.show(&format!("Recargado {}", entry.path), 1.5);
+        }
 
         match &mut state {
             AppState::Menu { selected } => {
-                if window.is_key_pressed(KeyboardKey::KEY_DOWN) { *selected = (*selected + 1) % 3; }
-                if window.is_key_pressed(KeyboardKey::KEY_UP) { if *selected == 0 { *selected = 2 } else { *selected -= 1; } }
-                if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
+                let entries = menu_entries(has_save);
+                let n = entries.len();
+                *selected = (*selected).min(n - 1);
+                if controls.pressed(&window, Action::MenuDown) { *selected = (*selected + 1) % n; }
+                if controls.pressed(&window, Action::MenuUp) { if *selected == 0 { *selected = n - 1 } else { *selected -= 1; } }
+                if controls.pressed(&window, Action::Confirm) {
+                    match entries[*selected] {
+                        MenuEntry::Continue => { load_requested = true; }
+                        MenuEntry::NewGame => {
+                            messages.show("Iniciando partida...", 0.5);
+                            new_game_seed = Some(rand::thread_rng().r#gen());
+                        }
+                        MenuEntry::Options => {
+                            state = AppState::OptionsMenu { row: 0, previous_selected: *selected, from_pause: false };
+                        }
+                        MenuEntry::Controls => { state = AppState::ControlsMenu { previous_selected: *selected, row: 0, listening: false }; }
+                        MenuEntry::Quit => { state = AppState::Exiting; }
+                    }
+                }
+            }
+            AppState::Paused { selected } => {
+                let n = PAUSE_OPTIONS.len();
+                if controls.pressed(&window, Action::MenuDown) { *selected = (*selected + 1) % n; }
+                if controls.pressed(&window, Action::MenuUp) { if *selected == 0 { *selected = n - 1 } else { *selected -= 1; } }
+                let mut resume = controls.pressed(&window, Action::Pause) || controls.pressed(&window, Action::Back);
+                if controls.pressed(&window, Action::Confirm) {
                     match *selected {
-                        0 => { message = Some(("Iniciando partida...".to_string(), 0.5)); }
-                        1 => {
-                            state = AppState::SoundMenu { volume: music_volume, previous_selected: *selected };
+                        0 => { resume = true; }
+                        1 => { new_game_seed = world.as_ref().map(|w| w.seed); }
+                        2 => {
+                            if let Some(w) = &world {
+                                match save_world(w, &settings::save_path()) {
+                                    Ok(()) => { has_save = true; from_save = true; messages.show("Partida guardada", 2.0); }
+                                    Err(e) => { messages.show(&format!("No se pudo guardar: {}", e), 3.0); }
+                                }
+                            }
                         }
-                        2 => { state = AppState::Exiting; }
+                        3 => { load_requested = true; }
+                        4 => { state = AppState::OptionsMenu { row: 0, previous_selected: *selected, from_pause: true }; }
+                        5 => { state = AppState::Menu { selected: 0 }; }
                         _ => {}
                     }
                 }
+                if resume { state = AppState::Playing; }
             }
-            AppState::SoundMenu { volume, previous_selected } => {
-                if window.is_key_down(KeyboardKey::KEY_LEFT) {
-                    *volume = (*volume - 0.6 * frame_dt).clamp(0.0, 1.0);
-                    audio.set_music_volume(*volume);
-                    music_volume = *volume;
+            AppState::OptionsMenu { row, previous_selected, from_pause } => {
+                if controls.pressed(&window, Action::MenuDown) { *row = (*row + 1) % OPTION_ROWS; }
+                if controls.pressed(&window, Action::MenuUp) { *row = if *row == 0 { OPTION_ROWS - 1 } else { *row - 1 }; }
+                let held = controls.down(&window, Action::MenuRight) as i32 - controls.down(&window, Action::MenuLeft) as i32;
+                let step = controls.pressed(&window, Action::MenuRight) as i32 - controls.pressed(&window, Action::MenuLeft) as i32;
+                let mut leave = controls.pressed(&window, Action::Back);
+                let toggle = step != 0 || controls.pressed(&window, Action::Confirm);
+                let buses = Bus::ALL.len();
+                match *row {
+                    r if r < buses => {
+                        let bus = Bus::ALL[r];
+                        let v = settings.volume_mut(bus);
+                        *v = (*v + 0.6 * frame_dt * held as f32).clamp(0.0, 1.0);
+                        audio.set_bus_volume(bus, *v);
+                    }
+                    4 if toggle => {
+                        settings.muted = !settings.muted;
+                        audio.set_muted(settings.muted);
+                    }
+                    5 if step != 0 => {
+                        let n = RESOLUTIONS.len() as i32;
+                        settings.set_resolution((settings.resolution_index() as i32 + step).rem_euclid(n) as usize);
+                        window.set_window_size(settings.window_w, settings.window_h);
+                        framebuffer = Framebuffer::new(settings.window_w as u32, settings.window_h as u32, Color::RAYWHITE);
+                    }
+                    6 if step != 0 => {
+                        settings.fov_deg = (settings.fov_deg + 5.0 * step as f32).clamp(45.0, 100.0);
+                        if let Some(w) = world.as_mut() { w.player.fov = settings.fov(); }
+                    }
+                    7 if step != 0 => {
+                        settings.render_scale = (settings.render_scale as i32 + step).clamp(1, 6) as usize;
+                    }
+                    8 if step != 0 => {
+                        settings.texture_filter = settings.texture_filter.cycle(step);
+                        texmgr.filter = settings.texture_filter;
+                    }
+                    9 => {
+                        settings.mouse_sensitivity = (settings.mouse_sensitivity + 0.002 * frame_dt * held as f32).clamp(0.0005, 0.01);
+                        controls.look.sensitivity = settings.mouse_sensitivity;
+                    }
+                    10 if toggle => {
+                        settings.invert_mouse = !settings.invert_mouse;
+                        controls.look.invert = settings.invert_mouse;
+                    }
+                    _ => {}
                 }
-                if window.is_key_down(KeyboardKey::KEY_RIGHT) {
-                    *volume = (*volume + 0.6 * frame_dt).clamp(0.0, 1.0);
-                    audio.set_music_volume(*volume);
-                    music_volume = *volume;
+                if controls.pressed(&window, Action::Confirm) && *row != 4 && *row != 10 { leave = true; }
+                if leave {
+                    if let Err(e) = settings.save(&settings_path) {
+                        eprintln!("Ajustes: no se pudo guardar '{}': {}", settings_path.display(), e);
+                    }
+                    state = if *from_pause { AppState::Paused { selected: *previous_selected } } else { AppState::Menu { selected: *previous_selected } };
                 }
-                if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
-                    state = AppState::Menu { selected: *previous_selected };
+            }
+            AppState::ControlsMenu { previous_selected, row, listening } => {
+                let actions = controls_rows();
+                let rows = actions.len() + 2;
+                if *listening {
+                    if let Some(key) = window.get_key_pressed() {
+                        controls.keymap.rebind(actions[*row], key);
+                        *listening = false;
+                    }
+                } else {
+                    if controls.pressed(&window, Action::MenuDown) { *row = (*row + 1) % rows; }
+                    if controls.pressed(&window, Action::MenuUp) { *row = if *row == 0 { rows - 1 } else { *row - 1 }; }
+                    let mut leave = controls.pressed(&window, Action::Back);
+                    if controls.pressed(&window, Action::Confirm) {
+                        if *row < actions.len() {
+                            while window.get_key_pressed().is_some() {}
+                            *listening = true;
+                        } else {
+                            match *row - actions.len() {
+                                0 => { controls.keymap = Keymap::defaults(); }
+                                1 => { leave = true; }
+                                _ => {}
+                            }
+                        }
+                    }
+                    if leave {
+                        messages.use_item_key = controls.keymap.describe(Action::UseItem);
+                        let keymap_path = settings::keymap_path();
+                        if let Err(e) = controls.keymap.save(&keymap_path) {
+                            eprintln!("Keymap: no se pudo guardar '{}': {}", keymap_path.display(), e);
+                        }
+                        state = AppState::Menu { selected: *previous_selected };
+                    }
                 }
             }
             AppState::Playing => {
-                if let Some(pl) = player.as_mut() {
-                    let input_dt = frame_dt.min(FIXED_DT);
-                    if let Some(msg) = process_events(&mut window, pl, &mut maze, block_size, input_dt, &mut goal_unlocked, &audio) {
-                        message = Some((msg, 2.0));
-                    }
+                if controls.pressed(&window, Action::ToggleMouse) {
+                    let captured = !controls.look.captured;
+                    set_cursor_captured(&mut window, &mut controls, captured);
+                }
+                if controls.pressed(&window, Action::Map) { show_map = !show_map; }
+                if playback.is_none() { pending.merge(controls.sample(&window)); }
+                if playback.is_none() && controls.pressed(&window, Action::Pause) {
+                    state = AppState::Paused { selected: 0 };
                 }
             }
             AppState::Victory => {
-                if window.is_key_pressed(KeyboardKey::KEY_ENTER) { state = AppState::Menu { selected: 0 }; }
+                if controls.pressed(&window, Action::Confirm) { state = AppState::Menu { selected: 0 }; }
             }
             AppState::GameOver => {
-                if window.is_key_pressed(KeyboardKey::KEY_ENTER) { state = AppState::Menu { selected: 0 }; }
+                if controls.pressed(&window, Action::Confirm) { state = AppState::Menu { selected: 0 }; }
             }
             AppState::Exiting => { break 'main_loop; }
         }
 
-        while accumulator >= FIXED_DT {
-            match &mut state {
-                AppState::Menu { selected } => {
-                    if window.is_key_pressed(KeyboardKey::KEY_ENTER) && *selected == 0 {
-                        let w = 12usize; let h = 10usize;
-                        let raw = generate_maze_text(w,h);
-                        maze = expand_maze(&raw, 2);
-
-                        block_size = {
-                            let bw = (WINDOW_W as usize) / maze[0].len();
-                            let bh = (WINDOW_H as usize) / maze.len();
-                            std::cmp::max(6, std::cmp::min(bw, bh))
-                        };
-
-                        fog = vec![vec![false; maze[0].len()]; maze.len()];
-
-                        let mut orig_px = 1usize; let mut orig_py = 1usize;
-                        'find_p: for (r,row) in maze.iter().enumerate() {
-                            for (c,&ch) in row.iter().enumerate() {
-                                if ch == 'p' { orig_px = c; orig_py = r; break 'find_p; }
-                            }
-                        }
-
-                        let rows = maze.len();
-                        let cols = maze[0].len();
-                        let mut reachable = vec![vec![false; cols]; rows];
-                        use std::collections::VecDeque;
-                        let mut q: VecDeque<(usize,usize)> = VecDeque::new();
-                        for r in 0..rows {
-                            for c in 0..cols {
-                                if r==0 || r==rows-1 || c==0 || c==cols-1 {
-                                    if maze[r][c] == ' ' {
-                                        reachable[r][c] = true;
-                                        q.push_back((r,c));
-                                    }
-                                }
-                            }
-                        }
-                        while let Some((r,c)) = q.pop_front() {
-                            let neigh = [(1isize,0),( -1,0),(0,1),(0,-1)];
-                            for (dy,dx) in neigh.iter() {
-                                let nr = r as isize + dy;
-                                let nc = c as isize + dx;
-                                if nr >= 0 && nc >= 0 && (nr as usize) < rows && (nc as usize) < cols {
-                                    let (nr, nc) = (nr as usize, nc as usize);
-                                    if !reachable[nr][nc] && maze[nr][nc] == ' ' {
-                                        reachable[nr][nc] = true;
-                                        q.push_back((nr,nc));
-                                    }
-                                }
-                            }
-                        }
-
-                        let mut spawn_x = orig_px;
-                        let mut spawn_y = orig_py;
-                        if !reachable[orig_py][orig_px] {
-                            let mut best: Option<(usize,usize)> = None;
-                            let mut bestd = usize::MAX;
-                            for r in 0..rows {
-                                for c in 0..cols {
-                                    if reachable[r][c] {
-                                        let d = ((r as isize - orig_py as isize).abs() + (c as isize - orig_px as isize).abs()) as usize;
-                                        if d < bestd {
-                                            bestd = d;
-                                            best = Some((c,r));
-                                        }
-                                    }
-                                }
-                            }
-                            if let Some((cx,cy)) = best {
-                                spawn_x = cx; spawn_y = cy;
-                            } else {
-                                'fallback_find: for r in 0..rows {
-                                    for c in 0..cols {
-                                        if maze[r][c] == ' ' { spawn_x = c; spawn_y = r; break 'fallback_find; }
-                                    }
-                                }
-                            }
-                        }
-
-                        maze[spawn_y][spawn_x] = ' ';
-
-                        let goal_x = if cols >= 3 { cols - 2 } else { cols.saturating_sub(1) };
-                        let goal_y = if rows >= 3 { rows - 2 } else { rows.saturating_sub(1) };
-                        for r in 0..rows { for c in 0..cols { if maze[r][c] == 'g' { maze[r][c] = ' '; } } }
-                        maze[goal_y][goal_x] = 'g';
-                        let neigh = [(1isize,0isize),(-1,0),(0,1),(0,-1)];
-                        for &(dx,dy) in neigh.iter() {
-                            let nx = goal_x as isize + dx;
-                            let ny = goal_y as isize + dy;
-                            if nx >= 0 && ny >= 0 && (ny as usize) < rows && (nx as usize) < cols {
-                                let (nxu, nyu) = (nx as usize, ny as usize);
-                                if maze[nyu][nxu] != 'g' { maze[nyu][nxu] = 'D'; }
-                            }
-                        }
-
-                        let cx = (spawn_x * block_size) as f32 + (block_size as f32)/2.0;
-                        let cy = (spawn_y * block_size) as f32 + (block_size as f32)/2.0;
-                        player = Some(Player::new(cx, cy, 0.0, PI/3.0));
-                        goal_unlocked = false;
-
-                        enemies.clear(); medkits.clear(); keys.clear(); bins.clear();
-                        let mut spawn1 = (cols.saturating_sub(3), 1usize);
-                        'outer1: for r in 1..rows-1 { for c in (1..cols-1).rev() { if maze[r][c] == ' ' { spawn1=(c,r); break 'outer1; } } }
-                        let mut spawn2 = (1usize, rows.saturating_sub(3));
-                        'outer2: for r in (1..rows-1).rev() { for c in 1..cols-1 { if maze[r][c] == ' ' { spawn2=(c,r); break 'outer2; } } }
-                        enemies.push(Enemy::new(spawn1, block_size, 0, 18.0));
-                        enemies.push(Enemy::new(spawn2, block_size, 0, 18.0));
-
-                        use rand::{thread_rng, Rng};
-                        let mut rng = thread_rng();
-                        let mut placed = 0; let want_medkits = 6usize;
-                        while placed < want_medkits {
-                            let r = rng.gen_range(1..rows-1);
-                            let c = rng.gen_range(1..cols-1);
-                            if maze[r][c] == ' ' {
-                                if !medkits.iter().any(|m| m.cell == (c,r)) {
-                                    medkits.push(Medkit { cell:(c,r), taken:false });
-                                    placed += 1;
-                                }
-                            }
-                        }
-
-                        let mut placed_key = false;
-                        for _ in 0..300 {
-                            let r = rng.gen_range(1..rows-1);
-                            let c = rng.gen_range(1..cols-1);
-                            if maze[r][c] == ' ' { keys.push(PickupKey { cell:(c,r), taken:false }); placed_key=true; break; }
-                        }
-                        if !placed_key { keys.push(PickupKey { cell:(cols/2, rows/2), taken:false }); }
-
-                        let mut placed_bin=false;
-                        for _ in 0..300 {
-                            let r = rng.gen_range(1..rows-1); let c = rng.gen_range(1..cols-1);
-                            if maze[r][c] == ' ' { bins.push(PickupBinocular { cell:(c,r), taken:false }); placed_bin=true; break; }
-                        }
-                        if !placed_bin { bins.push(PickupBinocular { cell:(cols/2+1, rows/2), taken:false }); }
-
-                        if let Some(pl) = &player {
-                            let pr = (pl.pos.y as usize) / block_size;
-                            let pc = (pl.pos.x as usize) / block_size;
-                            reveal_fog(&mut fog, pc, pr, &maze, 2);
-                        }
+        if let Some(seed) = new_game_seed.take() {
+            if let Some(rec) = recording.take() { save_replay(&rec); }
+            recording = if playback.is_none() { Some(Replay::new(seed)) } else { None };
+            let mut w = GameWorld::new(seed);
+            w.player.fov = settings.fov();
+            world = Some(w);
+            from_save = false;
+            stats = Stats::default();
+            messages.show("Cada puerta de color necesita su llave. La llave dorada abre la meta", 4.0);
+            state = AppState::Playing;
+        }
 
-                        message = Some(("Recoge la llave antes de abrir la puerta que protege la meta".to_string(), 4.0));
-                        state = AppState::Playing;
-                    }
+        if load_requested {
+            load_requested = false;
+            match load_world(&settings::save_path()) {
+                Ok(mut w) => {
+                    if let Some(rec) = recording.take() { save_replay(&rec); }
+                    w.player.fov = settings.fov();
+                    world = Some(w);
+                    from_save = true;
+                    stats = Stats::default();
+                    messages.show("Partida cargada", 2.0);
+                    state = AppState::Playing;
                 }
+                Err(e) => { messages.show(&format!("No se pudo cargar: {}", e), 3.0); }
+            }
+        }
 
-                AppState::Playing => {
-                    if let Some(pl) = player.as_mut() {
-                        pl.update_timers(FIXED_DT);
+        while accumulator >= FIXED_DT && matches!(state, AppState::Playing) {
+            let frame = match playback.as_mut() {
+                Some(r) => r.next_frame(),
+                None => Some(pending.take_tick()),
+            };
+            if let Some(rec) = recording.as_mut() && let Some(f) = frame { rec.record(f); }
+            if frame.is_none() {
+                playback = None;
+                messages.show("Fin de la repeticion", 2.5);
+                state = AppState::Menu { selected: 0 };
+            }
 
-                        let pr = (pl.pos.y as usize) / block_size;
-                        let pc = (pl.pos.x as usize) / block_size;
-                        reveal_fog(&mut fog, pc, pr, &maze, 2);
+            if let Some(w) = world.as_mut() && let Some(frame) = frame {
+                bus.publish_all(w.step(&frame, FIXED_DT));
+            }
+            for event in bus.dispatch(&mut [&mut SfxSubscriber(&mut audio), &mut messages, &mut stats]) {
+                match event {
+                    GameEvent::Victory => { state = AppState::Victory; }
+                    GameEvent::Death => { state = AppState::GameOver; }
+                    _ => {}
+                }
+                if matches!(event, GameEvent::Victory | GameEvent::Death) && from_save && playback.is_none() {
+                    delete_save(&settings::save_path());
+                    has_save = false;
+                    from_save = false;
+                }
+            }
 
-                        for e in enemies.iter_mut() {
-                            let attacked = e.update(&maze, block_size, &pl.pos, FIXED_DT);
-                            if attacked {
-                                pl.apply_damage(50.0);
-                                audio.play_sfx("assets/sfx_hurt.ogg", 0.3);
-                            }
-                        }
+            accumulator -= FIXED_DT;
+        }
 
-                        let player_cell = ((pl.pos.x as usize) / block_size, (pl.pos.y as usize) / block_size);
-                        for m in medkits.iter_mut() {
-                            if !m.taken && m.cell == player_cell {
-                                m.taken = true; pl.pickup_medkit(25.0); message = Some(("Medkit recogido".to_string(), 2.0));
-                            }
-                        }
-                        for k in keys.iter_mut() {
-                            if !k.taken && k.cell == player_cell { k.taken = true; pl.pickup_key(); message = Some(("Has recogido la llave!".to_string(), 3.0)); }
-                        }
-                        for b in bins.iter_mut() {
-                            if !b.taken && b.cell == player_cell { b.taken = true; pl.pickup_binoculars(60.0); message = Some(("Binoculares activados 60s".to_string(), 3.0)); }
-                        }
+        match (&state, &world) {
+            (AppState::Playing, Some(w)) => audio.update_emitters(&spatial::enemy_emitters(w)),
+            _ => audio.update_emitters(&[]),
+        }
 
-                        if pl.health <= 0.0 {
-                            audio.play_sfx("assets/sfx_gameover.ogg", 0.3);
-                            state = AppState::GameOver;
-                        }
+        let nearest = world.as_ref().and_then(|w| w.nearest_enemy_path());
+        match &state {
+            AppState::Playing | AppState::Paused { .. } | AppState::OptionsMenu { from_pause: true, .. } => {
+                audio.set_music(music::gameplay_state(nearest), music::tension_for(nearest));
+            }
+            AppState::Victory => audio.set_music(MusicState::Victory, 0.0),
+            AppState::GameOver => audio.set_music(MusicState::GameOver, 0.0),
+            _ => audio.set_music(MusicState::Menu, 0.0),
+        }
 
-                        let i = (pl.pos.x as usize) / block_size;
-                        let j = (pl.pos.y as usize) / block_size;
-                        if j < maze.len() && i < maze[j].len() && maze[j][i] == 'g' {
-                            if pl.has_key || goal_unlocked {
-                                audio.play_sfx("assets/sfx_victory.ogg", 0.3);
-                                state = AppState::Victory;
-                            } else {
-                                message = Some(("Necesitas una llave para entrar a la meta".to_string(), 2.5));
-                            }
-                        }
-                    }
+        let next = state.screen();
+        if next != screen {
+            match next {
+                Screen::Playing => {
+                    accumulator = 0.0;
+                    pending = InputFrame::default();
+                    audio.duck_music(1.0);
+                    set_cursor_captured(&mut window, &mut controls, true);
+                }
+                Screen::Paused => {
+                    audio.duck_music(PAUSE_DUCK);
+                    set_cursor_captured(&mut window, &mut controls, false);
+                }
+                _ => {
+                    audio.duck_music(1.0);
+                    if controls.look.captured { set_cursor_captured(&mut window, &mut controls, false); }
+                    if let Some(rec) = recording.take() { save_replay(&rec); }
                 }
-
-                _ => {}
             }
-
-            accumulator -= FIXED_DT;
+            screen = next;
         }
 
         match &state {
             AppState::Menu { selected } => {
-                draw_menu(&mut window, &raylib_thread, *selected);
+                draw_menu(&mut window, &raylib_thread, &menu_entries(has_save), *selected, messages.text());
+            }
+            AppState::Paused { selected } => {
+                draw_pause_menu(&mut window, &raylib_thread, *selected, messages.text());
             }
-            AppState::SoundMenu { volume, .. } => {
-                draw_sound_menu(&mut window, &raylib_thread, *volume);
+            AppState::OptionsMenu { row, .. } => {
+                draw_options_menu(&mut window, &raylib_thread, &settings, *row);
+            }
+            AppState::ControlsMenu { row, listening, .. } => {
+                draw_controls_menu(&mut window, &raylib_thread, &controls, *row, *listening);
             }
             AppState::Playing => {
-                if let Some(pl) = &player {
+                if let Some(w) = &world {
+                    let pl = &w.player;
+                    let (maze, doors, enemies, pickups, fog, block_size) = (&w.maze, &w.doors, &w.enemies, &w.pickups, &w.fog, w.block_size);
                     framebuffer.clear();
-                    let wall_distances = render_world_textured(&mut framebuffer, &maze, &pl, block_size, RENDER_SCALE, &texmgr);
+                    let depths = render_world_textured(&mut framebuffer, maze, doors, &pl, block_size, settings.render_scale, &texmgr);
 
                     let margin = 10;
                     let stamina_h = 18;
@@ -732,13 +981,14 @@ fn main() {
                     let minimap_offset_x = 10usize;
                     let minimap_offset_y = hud_h + 10usize;
                     let mm_w = 180usize; let mm_h = 140usize;
-                    draw_minimap_with_fog(&mut framebuffer, &maze, &fog, &pl, &enemies, &medkits, &keys, &bins, mm_w, mm_h, minimap_offset_x, minimap_offset_y, block_size);
+                    if show_map { draw_minimap_with_fog(&mut framebuffer, maze, fog, &pl, enemies, pickups, doors, mm_w, mm_h, minimap_offset_x, minimap_offset_y, block_size); }
 
                     let screen_w_px = framebuffer.width() as usize;
-                    let num_cols = (screen_w_px / RENDER_SCALE).max(1);
+                    let num_cols = (screen_w_px / settings.render_scale).max(1);
                     let proj_plane_dist = (num_cols as f32 / 2.0) / (pl.fov / 2.0).tan();
-                    let hh = framebuffer.height() as f32 / 2.0;
-                    let mut entries: Vec<(&Texture2D, f32, i32, i32, f32)> = Vec::new();
+                    let hh = pl.horizon(framebuffer.height() as f32);
+                    let eye = pl.eye_height();
+                    let mut entries: Vec<SpriteEntry> = Vec::new();
 
                     for e in enemies.iter() {
                         let dx = e.pos.x - pl.pos.x; let dy = e.pos.y - pl.pos.y;
@@ -751,25 +1001,26 @@ fn main() {
                         let corrected = dist * rel.cos().abs().max(1e-6);
                         let screen_col = (rel / (pl.fov/2.0)) * (num_cols as f32 / 2.0) + (num_cols as f32 / 2.0);
                         if !screen_col.is_finite() { continue; }
-                        let col_idx = screen_col.round() as isize;
-                        if col_idx < 0 || col_idx >= wall_distances.len() as isize { continue; }
-                        let wall_d = wall_distances[col_idx as usize];
-                        if corrected > wall_d { continue; }
                         let sprite_h_px = (block_size as f32 / corrected) * proj_plane_dist;
                         if !sprite_h_px.is_finite() || sprite_h_px <= 0.0 { continue; }
                         let tex_h = mimikyu_tex.height() as f32;
                         if tex_h <= 0.0 { continue; }
                         let scale = sprite_h_px / tex_h;
-                        let screen_x_px = screen_col * (RENDER_SCALE as f32);
-                        let draw_x = (screen_x_px - (mimikyu_tex.width() as f32 * scale)/2.0).round() as i32;
-                        let draw_y = (hh - sprite_h_px/2.0).round() as i32;
-                        entries.push((&mimikyu_tex, scale, draw_x, draw_y, corrected));
+                        let screen_x_px = screen_col * (settings.render_scale as f32);
+                        let draw_x = (screen_x_px - (mimikyu_tex.width() as f32 * scale)/2.0).round();
+                        let draw_y = (hh + (eye - 0.5) * sprite_h_px - sprite_h_px/2.0).round();
+                        entries.push((&mimikyu_tex, scale, draw_x, draw_y, corrected, Color::WHITE));
                     }
 
-                    for m in medkits.iter() {
-                        if m.taken { continue; }
-                        let mx = (m.cell.0 * block_size) as f32 + (block_size as f32)/2.0;
-                        let my = (m.cell.1 * block_size) as f32 + (block_size as f32)/2.0;
+                    for p in pickups.iter() {
+                        if p.taken { continue; }
+                        let tex = match p.item {
+                            Item::Medkit => &medkit_tex,
+                            Item::Key(_) => &key_tex,
+                            Item::Binoculars => &binocular_tex,
+                        };
+                        let mx = (p.cell.0 * block_size) as f32 + (block_size as f32)/2.0;
+                        let my = (p.cell.1 * block_size) as f32 + (block_size as f32)/2.0;
                         let dx = mx - pl.pos.x; let dy = my - pl.pos.y;
                         let dist = (dx*dx + dy*dy).sqrt();
                         let angle_to = dy.atan2(dx);
@@ -779,145 +1030,58 @@ fn main() {
                         if rel.abs() > pl.fov/2.0 { continue; }
                         let corrected = dist * rel.cos().abs().max(1e-6);
                         let screen_col = (rel / (pl.fov/2.0)) * (num_cols as f32 / 2.0) + (num_cols as f32 / 2.0);
-                        let col_idx = screen_col.round() as isize;
-                        if col_idx < 0 || col_idx >= wall_distances.len() as isize { continue; }
-                        let wall_d = wall_distances[col_idx as usize];
-                        if corrected > wall_d { continue; }
-                        let sprite_h_px = (block_size as f32 / corrected) * proj_plane_dist * 0.6;
-                        let tex_h = medkit_tex.height() as f32;
-                        if tex_h <= 0.0 { continue; }
-                        let scale = sprite_h_px / tex_h;
-                        let screen_x_px = screen_col * (RENDER_SCALE as f32);
-                        let draw_x = (screen_x_px - (medkit_tex.width() as f32 * scale)/2.0).round() as i32;
-                        let draw_y = (hh - sprite_h_px/2.0).round() as i32;
-                        entries.push((&medkit_tex, scale, draw_x, draw_y, corrected));
-                    }
-
-                    for k in keys.iter() {
-                        if k.taken { continue; }
-                        let kx = (k.cell.0 * block_size) as f32 + (block_size as f32)/2.0;
-                        let ky = (k.cell.1 * block_size) as f32 + (block_size as f32)/2.0;
-                        let dx = kx - pl.pos.x; let dy = ky - pl.pos.y;
-                        let dist = (dx*dx + dy*dy).sqrt();
-                        let angle_to = dy.atan2(dx);
-                        let mut rel = angle_to - pl.a;
-                        while rel > std::f32::consts::PI { rel -= 2.0*std::f32::consts::PI; }
-                        while rel < -std::f32::consts::PI { rel += 2.0*std::f32::consts::PI; }
-                        if rel.abs() > pl.fov/2.0 { continue; }
-                        let corrected = dist * rel.cos().abs().max(1e-6);
-                        let screen_col = (rel / (pl.fov/2.0)) * (num_cols as f32 / 2.0) + (num_cols as f32 / 2.0);
-                        let col_idx = screen_col.round() as isize;
-                        if col_idx < 0 || col_idx >= wall_distances.len() as isize { continue; }
-                        let wall_d = wall_distances[col_idx as usize];
-                        if corrected > wall_d { continue; }
-                        let sprite_h_px = (block_size as f32 / corrected) * proj_plane_dist * 0.5;
-                        let tex_h = key_tex.height() as f32;
-                        if tex_h <= 0.0 { continue; }
-                        let scale = sprite_h_px / tex_h;
-                        let screen_x_px = screen_col * (RENDER_SCALE as f32);
-                        let draw_x = (screen_x_px - (key_tex.width() as f32 * scale)/2.0).round() as i32;
-                        let draw_y = (hh - sprite_h_px/2.0).round() as i32;
-                        entries.push((&key_tex, scale, draw_x, draw_y, corrected));
-                    }
-
-                    for b in bins.iter() {
-                        if b.taken { continue; }
-                        let bx = (b.cell.0 * block_size) as f32 + (block_size as f32)/2.0;
-                        let by = (b.cell.1 * block_size) as f32 + (block_size as f32)/2.0;
-                        let dx = bx - pl.pos.x; let dy = by - pl.pos.y;
-                        let dist = (dx*dx + dy*dy).sqrt();
-                        let angle_to = dy.atan2(dx);
-                        let mut rel = angle_to - pl.a;
-                        while rel > std::f32::consts::PI { rel -= 2.0*std::f32::consts::PI; }
-                        while rel < -std::f32::consts::PI { rel += 2.0*std::f32::consts::PI; }
-                        if rel.abs() > pl.fov/2.0 { continue; }
-                        let corrected = dist * rel.cos().abs().max(1e-6);
-                        let screen_col = (rel / (pl.fov/2.0)) * (num_cols as f32 / 2.0) + (num_cols as f32 / 2.0);
-                        let col_idx = screen_col.round() as isize;
-                        if col_idx < 0 || col_idx >= wall_distances.len() as isize { continue; }
-                        let wall_d = wall_distances[col_idx as usize];
-                        if corrected > wall_d { continue; }
-                        let sprite_h_px = (block_size as f32 / corrected) * proj_plane_dist * 0.5;
-                        let tex_h = binocular_tex.height() as f32;
+                        if !screen_col.is_finite() { continue; }
+                        let sprite_h_px = (block_size as f32 / corrected) * proj_plane_dist * p.sprite_scale();
+                        let tex_h = tex.height() as f32;
                         if tex_h <= 0.0 { continue; }
                         let scale = sprite_h_px / tex_h;
-                        let screen_x_px = screen_col * (RENDER_SCALE as f32);
-                        let draw_x = (screen_x_px - (binocular_tex.width() as f32 * scale)/2.0).round() as i32;
-                        let draw_y = (hh - sprite_h_px/2.0).round() as i32;
-                        entries.push((&binocular_tex, scale, draw_x, draw_y, corrected));
-                    }
-
-                    let rows_m = maze.len(); let cols_m = maze[0].len();
-                    for yy in 0..rows_m {
-                        for xx in 0..cols_m {
-                            if maze[yy][xx] == 'D' {
-                                let dx_cell = (xx * block_size) as f32 + (block_size as f32)/2.0;
-                                let dy_cell = (yy * block_size) as f32 + (block_size as f32)/2.0;
-                                let dxp = dx_cell - pl.pos.x; let dyp = dy_cell - pl.pos.y;
-                                let dist = (dxp*dxp + dyp*dyp).sqrt();
-                                let angle_to = dyp.atan2(dxp);
-                                let mut rel = angle_to - pl.a;
-                                while rel > std::f32::consts::PI { rel -= 2.0*std::f32::consts::PI; }
-                                while rel < -std::f32::consts::PI { rel += 2.0*std::f32::consts::PI; }
-                                if rel.abs() > pl.fov/2.0 { continue; }
-                                let corrected = dist * rel.cos().abs().max(1e-6);
-                                let screen_col = (rel / (pl.fov/2.0)) * (num_cols as f32 / 2.0) + (num_cols as f32 / 2.0);
-                                let col_idx = screen_col.round() as isize;
-                                if col_idx < 0 || col_idx >= wall_distances.len() as isize { continue; }
-                                let wall_d = wall_distances[col_idx as usize];
-                                if corrected > wall_d { continue; }
-                                let sprite_h_px = (block_size as f32 / corrected) * proj_plane_dist * 0.9;
-                                let tex_h = door_tex.height() as f32;
-                                if tex_h <= 0.0 { continue; }
-                                let scale = sprite_h_px / tex_h;
-                                let screen_x_px = screen_col * (RENDER_SCALE as f32);
-                                let draw_x = (screen_x_px - (door_tex.width() as f32 * scale)/2.0).round() as i32;
-                                let draw_y = (hh - sprite_h_px/2.0).round() as i32;
-                                entries.push((&door_tex, scale, draw_x, draw_y, corrected));
-                            }
-                        }
+                        let screen_x_px = screen_col * (settings.render_scale as f32);
+                        let draw_x = (screen_x_px - (tex.width() as f32 * scale)/2.0).round();
+                        let unit = sprite_h_px / p.sprite_scale();
+                        let draw_y = (hh + (eye - 0.5) * unit - sprite_h_px/2.0).round();
+                        entries.push((tex, scale, draw_x, draw_y, corrected, p.sprite_tint()));
                     }
 
                     entries.sort_by(|a,b| b.4.partial_cmp(&a.4).unwrap_or(std::cmp::Ordering::Equal));
-                    let sprite_draws: Vec<(&Texture2D, f32, i32, i32)> = entries.iter().map(|&(t,s,x,y,_d)| (t,s,x,y)).collect();
+                    let sprite_draws: Vec<SpriteDraw> = entries.iter().flat_map(|e| sprite_strips(e, &depths, settings.render_scale)).collect();
 
                     let mut fps_text = format!("FPS: {:.1}", fps);
                     if pl.binocular_timer > 0.0 { fps_text.push_str(&format!("   BIN: {}s", pl.binocular_timer.round() as i32)); }
-                    if let Some((ref msg, _t)) = message { fps_text.push_str(&format!("   MSG: {}", msg)); }
-
-                    framebuffer.swap_buffers_with_fps(
-                        &mut window,
-                        &raylib_thread,
-                        Some(&fps_text),
-                        Some((pl.stamina, pl.stamina_max)),
-                        Some((pl.health, pl.health_max)),
-                        Some((pl.shield, pl.shield_max)),
-                        Some(&sprite_draws),
+                    if let Some(msg) = messages.text() { fps_text.push_str(&format!("   MSG: {}", msg)); }
+
+                    let prompt_text = w.interact_target.and_then(|t| prompt(t, doors, pickups, pl, &controls.keymap.describe(Action::Use)));
+                    let inventory_hint = format!(
+                        "{}/{} cambiar objeto - {} usar",
+                        controls.keymap.describe(Action::PrevItem),
+                        controls.keymap.describe(Action::NextItem),
+                        controls.keymap.describe(Action::UseItem),
                     );
+                    let hud = Hud {
+                        fps_text: Some(&fps_text),
+                        stamina: Some((pl.stamina, pl.stamina_max)),
+                        health: Some((pl.health, pl.health_max)),
+                        shield: Some((pl.shield, pl.shield_max)),
+                        inventory: Some((pl.inventory.slots(), pl.inventory.active_index())),
+                        inventory_hint: Some(&inventory_hint),
+                        prompt: prompt_text.as_deref(),
+                    };
+                    framebuffer.swap_buffers_with_fps(&mut window, &raylib_thread, Some(&sprite_draws), &hud);
                 } else {
-                    draw_menu(&mut window, &raylib_thread, 0);
+                    draw_menu(&mut window, &raylib_thread, &menu_entries(has_save), 0, None);
                 }
             }
-            AppState::Victory => { draw_victory(&mut window, &raylib_thread, win_tex.as_ref());}
-            AppState::GameOver => { draw_gameover(&mut window, &raylib_thread, game_over_tex.as_ref());}
+            AppState::Victory => { draw_victory(&mut window, &raylib_thread, win_tex.as_ref(), &stats);}
+            AppState::GameOver => { draw_gameover(&mut window, &raylib_thread, game_over_tex.as_ref(), &stats);}
             AppState::Exiting => { break 'main_loop; }
         }
     }
+
+    if let Some(rec) = recording.take() { save_replay(&rec); }
 }
 
-fn reveal_fog(fog: &mut Vec<Vec<bool>>, cx: usize, cy: usize, maze: &Maze, r: i32) {
-    let rows = maze.len() as i32;
-    let cols = maze[0].len() as i32;
-    let cr = cy as i32;
-    let cc = cx as i32;
-    for dy in -r..=r {
-        for dx in -r..=r {
-            let ny = cr + dy;
-            let nx = cc + dx;
-            if nx >= 0 && ny >= 0 && nx < cols && ny < rows {
-                let dist2 = dx*dx + dy*dy;
-                if dist2 as f32 <= (r as f32) * (r as f32) { fog[ny as usize][nx as usize] = true; }
-            }
-        }
+fn save_replay(rec: &Replay) {
+    let path = settings::replay_path();
+    if let Err(e) = rec.save(&path) {
+        eprintln!("Replay: no se pudo guardar '{}': {}", path.display(), e);
     }
 }
@@ -12,3 +12,26 @@ pub fn load_maze(filename: &str) -> Maze {
         .map(|line| line.expect("Failed to read line").chars().collect())
         .collect()
 }
+
+pub const SEE_THROUGH: [char; 4] = ['t', 'b', 'v', 'r'];
+
+pub fn is_see_through(ch: char) -> bool {
+    SEE_THROUGH.contains(&ch)
+}
+
+pub const LOW_WALLS: [char; 2] = ['h', 'l'];
+pub const PILLAR: char = 'P';
+pub const MAX_WALL_HEIGHT: f32 = 1.6;
+
+pub fn wall_height(ch: char) -> f32 {
+    match ch {
+        'h' => 0.5,
+        'l' => 0.25,
+        PILLAR => MAX_WALL_HEIGHT,
+        _ => 1.0,
+    }
+}
+
+pub fn blocks_view(ch: char) -> bool {
+    !is_see_through(ch) && wall_height(ch) >= 1.0
+}
@@ -0,0 +1,43 @@
+use crate::events::{GameEvent, Subscriber};
+use crate::inventory::{key_color_name, Item};
+
+#[derive(Default)]
+pub struct Messages {
+    current: Option<(String, f32)>,
+    pub use_item_key: String,
+}
+
+impl Messages {
+    pub fn show(&mut self, text: &str, seconds: f32) {
+        self.current = Some((text.to_string(), seconds));
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if let Some((_, ref mut t)) = self.current {
+            *t -= dt;
+            if *t <= 0.0 { self.current = None; }
+        }
+    }
+
+    pub fn text(&self) -> Option<&str> {
+        self.current.as_ref().map(|(m, _)| m.as_str())
+    }
+}
+
+impl Subscriber for Messages {
+    fn on_event(&mut self, event: &GameEvent) {
+        let text = match event {
+            GameEvent::PickedUp(Item::Medkit) => "Medkit guardado en el inventario".to_string(),
+            GameEvent::PickedUp(Item::Binoculars) => format!("Binoculares guardados ({} para usar)", self.use_item_key),
+            GameEvent::PickedUp(item @ Item::Key(_)) => format!("Has recogido la {}!", item.label().to_lowercase()),
+            GameEvent::ItemUsed(Item::Medkit) => "Usaste un medkit".to_string(),
+            GameEvent::ItemUsed(Item::Binoculars) => "Binoculares activados 60s".to_string(),
+            GameEvent::ItemUsed(Item::Key(_)) | GameEvent::KeyNotUsable => "Las llaves se usan al llegar a su puerta".to_string(),
+            GameEvent::DoorOpened(lock) => format!("Usaste la llave {} para abrir una puerta.", key_color_name(*lock)),
+            GameEvent::DoorLocked(lock) => format!("Esta puerta necesita la llave {}.", key_color_name(*lock)),
+            GameEvent::GoalLocked => "Necesitas una llave para entrar a la meta".to_string(),
+            _ => return,
+        };
+        self.show(&text, 2.5);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::audio::SoundId;
+use crate::audio_backend::{AudioBackend, VoiceId};
+
+const FALLBACK_TRACK: &str = "fallback";
+const TENSION_STEM: &str = "tension";
+const CROSSFADE_SECS: f32 = 1.5;
+const TENSION_SPEED: f32 = 0.8;
+pub const CHASE_CELLS: usize = 5;
+pub const TENSION_CELLS: usize = 12;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MusicState {
+    Menu,
+    Exploring,
+    Chase,
+    Victory,
+    GameOver,
+}
+
+impl MusicState {
+    pub const ALL: [MusicState; 5] = [MusicState::Menu, MusicState::Exploring, MusicState::Chase, MusicState::Victory, MusicState::GameOver];
+
+    fn asset_id(self) -> &'static str {
+        match self {
+            MusicState::Menu => "menu",
+            MusicState::Exploring => "explore",
+            MusicState::Chase => "chase",
+            MusicState::Victory => "victory",
+            MusicState::GameOver => "gameover",
+        }
+    }
+
+    fn resolve(self, backend: &dyn AudioBackend) -> Option<&'static str> {
+        if backend.has_music(self.asset_id()) { return Some(self.asset_id()); }
+        match self {
+            MusicState::Victory | MusicState::GameOver => None,
+            _ => backend.has_music(FALLBACK_TRACK).then_some(FALLBACK_TRACK),
+        }
+    }
+}
+
+pub fn tension_for(path_cells: Option<usize>) -> f32 {
+    match path_cells {
+        Some(d) => 1.0 - (d as f32 / TENSION_CELLS as f32).clamp(0.0, 1.0),
+        None => 0.0,
+    }
+}
+
+pub fn gameplay_state(path_cells: Option<usize>) -> MusicState {
+    match path_cells {
+        Some(d) if d <= CHASE_CELLS => MusicState::Chase,
+        _ => MusicState::Exploring,
+    }
+}
+
+fn approach(value: f32, target: f32, step: f32) -> f32 {
+    if value < target { (value + step).min(target) } else { (value - step).max(target) }
+}
+
+struct Track {
+    path: &'static str,
+    voice: VoiceId,
+    level: f32,
+}
+
+pub struct MusicController {
+    tracks: Vec<Track>,
+    routes: Vec<(MusicState, Option<&'static str>)>,
+    state: MusicState,
+    active: Option<&'static str>,
+    tension_voice: Option<VoiceId>,
+    tension: f32,
+    tension_target: f32,
+}
+
+impl MusicController {
+    pub fn new(backend: &mut dyn AudioBackend) -> Self {
+        let mut tracks: Vec<Track> = Vec::new();
+        let mut routes = Vec::new();
+        for state in MusicState::ALL {
+            let path = state.resolve(backend);
+            routes.push((state, path));
+            let Some(path) = path else { continue; };
+            if tracks.iter().any(|t| t.path == path) { continue; }
+            if let Some(voice) = backend.play_music(path, 0.0) {
+                tracks.push(Track { path, voice, level: 0.0 });
+            }
+        }
+        let active = routes[0].1;
+
+        let tension_voice = if backend.has_music(TENSION_STEM) {
+            backend.play_music(TENSION_STEM, 0.0)
+        } else {
+            backend.play_loop(SoundId::Tension, 0.0)
+        };
+
+        MusicController { tracks, routes, state: MusicState::Menu, active, tension_voice, tension: 0.0, tension_target: 0.0 }
+    }
+
+    pub fn restart(&mut self, backend: &mut dyn AudioBackend) {
+        for t in self.tracks.iter() { backend.stop(t.voice); }
+        if let Some(voice) = self.tension_voice { backend.stop(voice); }
+        let mut fresh = MusicController::new(backend);
+        fresh.set_state(self.state);
+        fresh.tension_target = self.tension_target;
+        *self = fresh;
+    }
+
+    pub fn set_state(&mut self, state: MusicState) {
+        if state == self.state { return; }
+        self.state = state;
+        self.active = self.routes.iter().find(|(s, _)| *s == state).and_then(|(_, p)| *p);
+    }
+
+    pub fn set_tension(&mut self, tension: f32) {
+        self.tension_target = tension.clamp(0.0, 1.0);
+    }
+
+    pub fn update(&mut self, backend: &mut dyn AudioBackend, dt: f32, volume: f32) {
+        for t in self.tracks.iter_mut() {
+            let target = if Some(t.path) == self.active { 1.0 } else { 0.0 };
+            t.level = approach(t.level, target, dt / CROSSFADE_SECS);
+        }
+        let tension_allowed = matches!(self.state, MusicState::Exploring | MusicState::Chase);
+        let target = if tension_allowed { self.tension_target } else { 0.0 };
+        self.tension = approach(self.tension, target, TENSION_SPEED * dt);
+        self.apply(backend, volume);
+    }
+
+    pub fn apply(&self, backend: &mut dyn AudioBackend, volume: f32) {
+        for t in self.tracks.iter() {
+            backend.set_volume(t.voice, t.level * volume);
+        }
+        if let Some(voice) = self.tension_voice {
+            backend.set_volume(voice, self.tension * volume);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tension_grows_as_enemies_get_closer() {
+        assert_eq!(tension_for(None), 0.0);
+        assert_eq!(tension_for(Some(TENSION_CELLS + 3)), 0.0);
+        assert!(tension_for(Some(2)) > tension_for(Some(8)));
+        assert_eq!(tension_for(Some(0)), 1.0);
+    }
+
+    #[test]
+    fn close_enemies_switch_to_chase() {
+        assert_eq!(gameplay_state(None), MusicState::Exploring);
+        assert_eq!(gameplay_state(Some(CHASE_CELLS + 1)), MusicState::Exploring);
+        assert_eq!(gameplay_state(Some(CHASE_CELLS)), MusicState::Chase);
+    }
+}
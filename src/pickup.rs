@@ -0,0 +1,36 @@
+use raylib::color::Color;
+use crate::inventory::{key_color, Item};
+
+pub struct Pickup {
+    pub cell: (usize, usize),
+    pub item: Item,
+    pub taken: bool,
+}
+
+impl Pickup {
+    pub fn new(cell: (usize, usize), item: Item) -> Self {
+        Pickup { cell, item, taken: false }
+    }
+
+    pub fn minimap_color(&self) -> Color {
+        match self.item {
+            Item::Medkit => Color::SKYBLUE,
+            Item::Binoculars => Color::PURPLE,
+            Item::Key(id) => key_color(id),
+        }
+    }
+
+    pub fn sprite_scale(&self) -> f32 {
+        match self.item {
+            Item::Medkit => 0.6,
+            _ => 0.5,
+        }
+    }
+
+    pub fn sprite_tint(&self) -> Color {
+        match self.item {
+            Item::Key(id) => key_color(id),
+            _ => Color::WHITE,
+        }
+    }
+}
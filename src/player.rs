@@ -1,11 +1,16 @@
 use raylib::prelude::*;
 use std::f32::consts::PI;
+use crate::inventory::{Inventory, Item};
+
+pub const STEP_INTERVAL: f32 = 0.18;
+pub const MAX_PITCH: f32 = 0.35;
+const CROUCH_DROP: f32 = 0.2;
+const BOB_AMPLITUDE: f32 = 0.025;
 
 pub struct Player {
     pub pos: Vector2,  
     pub a: f32,        
     pub fov: f32,  
-    pub has_key: bool,
     pub health: f32,
     pub health_max: f32,
     pub shield: f32,
@@ -14,6 +19,10 @@ pub struct Player {
     pub stamina_max: f32,
     pub binocular_timer: f32,
     pub step_timer: f32, 
+    pub radius: f32,
+    pub pitch: f32,
+    pub crouch: f32,
+    pub inventory: Inventory,
 }
 
 impl Player {
@@ -22,7 +31,6 @@ impl Player {
             pos: Vector2::new(x, y),
             a: angle,
             fov,
-            has_key: false,
             health: 100.0,
             health_max: 100.0,
             shield: 0.0,
@@ -31,6 +39,10 @@ impl Player {
             stamina_max: 100.0,
             binocular_timer: 0.0,
             step_timer: 0.0,
+            radius: 0.0,
+            pitch: 0.0,
+            crouch: 0.0,
+            inventory: Inventory::new(),
         }
     }
 
@@ -46,6 +58,15 @@ impl Player {
         }
     }
 
+    pub fn eye_height(&self) -> f32 {
+        let bob = (self.step_timer / STEP_INTERVAL * PI).sin() * BOB_AMPLITUDE;
+        0.5 - CROUCH_DROP * self.crouch - bob
+    }
+
+    pub fn horizon(&self, screen_h: f32) -> f32 {
+        screen_h / 2.0 + self.pitch * screen_h
+    }
+
     pub fn apply_damage(&mut self, amount: f32) {
         let mut dmg = amount;
         if self.shield > 0.0 {
@@ -58,7 +79,7 @@ impl Player {
         }
     }
 
-    pub fn pickup_medkit(&mut self, amount: f32) {
+    pub fn heal(&mut self, amount: f32) {
         if self.health < self.health_max {
             self.health = (self.health + amount).min(self.health_max);
         } else {
@@ -66,9 +87,25 @@ impl Player {
         }
     }
 
-    pub fn pickup_key(&mut self) { self.has_key = true; }
+    pub fn pickup_item(&mut self, item: Item) { self.inventory.add(item); }
 
-    pub fn pickup_binoculars(&mut self, seconds: f32) {
+    pub fn activate_binoculars(&mut self, seconds: f32) {
         self.binocular_timer = self.binocular_timer.max(seconds);
     }
+
+    pub fn use_active_item(&mut self) -> Option<Item> {
+        let item = self.inventory.active_item()?;
+        match item {
+            Item::Medkit => {
+                self.inventory.remove_one(item);
+                self.heal(25.0);
+            }
+            Item::Binoculars => {
+                self.inventory.remove_one(item);
+                self.activate_binoculars(60.0);
+            }
+            Item::Key(_) => return None,
+        }
+        Some(item)
+    }
 }
@@ -0,0 +1,109 @@
+use std::fs;
+use std::path::Path;
+use crate::input::InputFrame;
+
+pub struct Replay {
+    pub seed: u64,
+    pub frames: Vec<InputFrame>,
+    cursor: usize,
+}
+
+fn flag(b: bool) -> u8 { if b { 1 } else { 0 } }
+
+fn frame_to_line(f: &InputFrame) -> String {
+    format!(
+        "{} {} {} {} {} {} {} {} {} {} {} {}",
+        f.forward, f.strafe, f.turn, f.look,
+        flag(f.sprint), flag(f.use_target), flag(f.use_item), flag(f.next_item), flag(f.prev_item),
+        f.tilt, f.pitch, flag(f.crouch)
+    )
+}
+
+fn frame_from_line(line: &str) -> Option<InputFrame> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() != 9 && parts.len() != 12 { return None; }
+    let num = |i: usize| parts.get(i).map_or(Some(0.0), |p| p.parse::<f32>().ok());
+    let bit = |i: usize| parts.get(i) == Some(&"1");
+    Some(InputFrame {
+        forward: num(0)?,
+        strafe: num(1)?,
+        turn: num(2)?,
+        look: num(3)?,
+        tilt: num(9)?,
+        pitch: num(10)?,
+        sprint: bit(4),
+        crouch: bit(11),
+        use_target: bit(5),
+        use_item: bit(6),
+        next_item: bit(7),
+        prev_item: bit(8),
+    })
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Replay { seed, frames: Vec::new(), cursor: 0 }
+    }
+
+    pub fn record(&mut self, frame: InputFrame) {
+        self.frames.push(frame);
+    }
+
+    pub fn next_frame(&mut self) -> Option<InputFrame> {
+        let frame = self.frames.get(self.cursor).copied();
+        self.cursor += 1;
+        frame
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut out = format!("seed {}\n", self.seed);
+        for f in self.frames.iter() {
+            out.push_str(&frame_to_line(f));
+            out.push('\n');
+        }
+        if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+        fs::write(path, out)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("no se pudo abrir '{}': {}", path.display(), e))?;
+        let mut lines = text.lines();
+        let seed = lines
+            .next()
+            .and_then(|l| l.strip_prefix("seed "))
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .ok_or_else(|| format!("'{}' no empieza con la semilla", path.display()))?;
+        let mut frames = Vec::new();
+        for (n, line) in lines.enumerate() {
+            let frame = frame_from_line(line).ok_or_else(|| format!("linea {} invalida en '{}'", n + 2, path.display()))?;
+            frames.push(frame);
+        }
+        Ok(Replay { seed, frames, cursor: 0 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut rec = Replay::new(99);
+        rec.record(InputFrame { forward: 1.0, strafe: -0.5, turn: 0.25, look: 0.125, sprint: true, use_target: true, ..Default::default() });
+        rec.record(InputFrame { tilt: -1.0, pitch: 0.0625, crouch: true, use_item: true, next_item: true, prev_item: true, ..Default::default() });
+        let path = std::env::temp_dir().join(format!("mansion_replay_{}.replay", std::process::id()));
+        rec.save(&path).unwrap();
+        let mut loaded = Replay::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.seed, 99);
+        assert_eq!(loaded.frames, rec.frames);
+        assert_eq!(loaded.next_frame(), Some(rec.frames[0]));
+    }
+
+    #[test]
+    fn reads_old_lines_without_camera_fields() {
+        let frame = frame_from_line("1 0 -0.5 0.01 1 0 0 1 0").unwrap();
+        assert_eq!(frame, InputFrame { forward: 1.0, turn: -0.5, look: 0.01, sprint: true, next_item: true, ..Default::default() });
+        assert_eq!(frame_from_line("1 0 0"), None);
+    }
+}
@@ -0,0 +1,368 @@
+use raylib::prelude::Vector2;
+use std::fs;
+use std::path::Path;
+use std::str::{FromStr, Lines};
+use crate::world::GameWorld;
+use crate::player::Player;
+use crate::enemy::Enemy;
+use crate::pickup::Pickup;
+use crate::door::{Door, DoorState};
+use crate::inventory::Item;
+
+pub const SAVE_VERSION: u32 = 2;
+const PLAYER_FIELDS_V1: usize = 14;
+const SAVE_MAGIC: &str = "MANSION_SAVE";
+
+fn item_id(item: Item) -> String {
+    match item {
+        Item::Key(id) => format!("key:{}", id),
+        Item::Medkit => "medkit".to_string(),
+        Item::Binoculars => "binoculars".to_string(),
+    }
+}
+
+fn item_from_id(id: &str) -> Option<Item> {
+    match id {
+        "medkit" => Some(Item::Medkit),
+        "binoculars" => Some(Item::Binoculars),
+        _ => id.strip_prefix("key:").and_then(|n| n.parse().ok()).map(Item::Key),
+    }
+}
+
+fn door_state_id(state: DoorState) -> u8 {
+    match state {
+        DoorState::Closed => 0,
+        DoorState::Opening => 1,
+        DoorState::Open => 2,
+        DoorState::Closing => 3,
+    }
+}
+
+fn door_state_from_id(id: u8) -> Option<DoorState> {
+    match id {
+        0 => Some(DoorState::Closed),
+        1 => Some(DoorState::Opening),
+        2 => Some(DoorState::Open),
+        3 => Some(DoorState::Closing),
+        _ => None,
+    }
+}
+
+fn flag(b: bool) -> u8 { if b { 1 } else { 0 } }
+
+pub fn save_world(world: &GameWorld, path: &Path) -> std::io::Result<()> {
+    let mut out = format!("{} {}\n", SAVE_MAGIC, SAVE_VERSION);
+    out.push_str(&format!("seed {}\n", world.seed));
+    out.push_str(&format!("block_size {}\n", world.block_size));
+    out.push_str(&format!("goal_unlocked {}\n", flag(world.goal_unlocked)));
+
+    out.push_str(&format!("maze {}\n", world.maze.len()));
+    for row in world.maze.iter() {
+        out.push_str(&row.iter().collect::<String>());
+        out.push('\n');
+    }
+    out.push_str(&format!("fog {}\n", world.fog.len()));
+    for row in world.fog.iter() {
+        out.push_str(&row.iter().map(|&v| if v { '1' } else { '0' }).collect::<String>());
+        out.push('\n');
+    }
+
+    let p = &world.player;
+    out.push_str(&format!(
+        "player {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}\n",
+        p.pos.x, p.pos.y, p.a, p.fov, p.health, p.health_max, p.shield, p.shield_max,
+        p.stamina, p.stamina_max, p.binocular_timer, p.step_timer, p.radius, p.pitch, p.crouch
+    ));
+    let slots = p.inventory.slots();
+    out.push_str(&format!("inventory {} {}\n", slots.len(), p.inventory.active_index()));
+    for slot in slots.iter() {
+        out.push_str(&format!("{} {}\n", item_id(slot.item), slot.count));
+    }
+
+    out.push_str(&format!("enemies {}\n", world.enemies.len()));
+    for e in world.enemies.iter() {
+        out.push_str(&format!(
+            "{} {} {} {} {} {} {} {} {}\n",
+            e.spawn_cell.0, e.spawn_cell.1, e.pos.x, e.pos.y, e.tex_index, e.speed, e.radius, e.path_timer, e.attack_cooldown
+        ));
+    }
+
+    out.push_str(&format!("pickups {}\n", world.pickups.len()));
+    for pk in world.pickups.iter() {
+        out.push_str(&format!("{} {} {} {}\n", pk.cell.0, pk.cell.1, item_id(pk.item), flag(pk.taken)));
+    }
+
+    out.push_str(&format!("doors {}\n", world.doors.len()));
+    for d in world.doors.iter() {
+        let auto = d.auto_close.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!(
+            "{} {} {} {} {} {} {} {} {}\n",
+            d.cell.0, d.cell.1, d.lock, flag(d.unlocked), door_state_id(d.state), d.open, flag(d.horizontal), auto, d.timer
+        ));
+    }
+
+    if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+    fs::write(path, out)
+}
+
+pub fn delete_save(path: &Path) {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => eprintln!("Partida: no se pudo borrar '{}': {}", path.display(), e),
+        _ => {}
+    }
+}
+
+struct Reader<'a> {
+    lines: Lines<'a>,
+    line_no: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn line(&mut self) -> Result<&'a str, String> {
+        self.line_no += 1;
+        self.lines.next().ok_or_else(|| format!("fin de archivo inesperado en la linea {}", self.line_no))
+    }
+
+    fn fields(&mut self, expected: usize) -> Result<Vec<&'a str>, String> {
+        let parts: Vec<&str> = self.line()?.split_whitespace().collect();
+        if parts.len() != expected {
+            return Err(format!("linea {}: se esperaban {} campos", self.line_no, expected));
+        }
+        Ok(parts)
+    }
+
+    fn section<T: FromStr>(&mut self, name: &str) -> Result<T, String> {
+        let parts = self.fields(2)?;
+        if parts[0] != name {
+            return Err(format!("linea {}: se esperaba '{}'", self.line_no, name));
+        }
+        self.parse(parts[1])
+    }
+
+    fn parse<T: FromStr>(&self, text: &str) -> Result<T, String> {
+        text.parse().map_err(|_| format!("linea {}: valor invalido '{}'", self.line_no, text))
+    }
+}
+
+pub fn load_world(path: &Path) -> Result<GameWorld, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("no se pudo abrir '{}': {}", path.display(), e))?;
+    let mut r = Reader { lines: text.lines(), line_no: 0 };
+
+    let header = r.fields(2)?;
+    if header[0] != SAVE_MAGIC {
+        return Err(format!("'{}' no es un archivo de partida", path.display()));
+    }
+    let version: u32 = r.parse(header[1])?;
+    if !(1..=SAVE_VERSION).contains(&version) {
+        return Err(format!("version de partida {} no soportada (se esperaba {})", version, SAVE_VERSION));
+    }
+
+    let seed: u64 = r.section("seed")?;
+    let block_size: usize = r.section("block_size")?;
+    let goal_unlocked = r.section::<u8>("goal_unlocked")? == 1;
+
+    let rows: usize = r.section("maze")?;
+    let mut maze = Vec::with_capacity(rows);
+    for _ in 0..rows { maze.push(r.line()?.chars().collect::<Vec<char>>()); }
+
+    let rows: usize = r.section("fog")?;
+    let mut fog = Vec::with_capacity(rows);
+    for _ in 0..rows { fog.push(r.line()?.chars().map(|c| c == '1').collect::<Vec<bool>>()); }
+
+    let f = r.fields(if version == 1 { PLAYER_FIELDS_V1 } else { PLAYER_FIELDS_V1 + 2 })?;
+    if f[0] != "player" { return Err(format!("linea {}: se esperaba 'player'", r.line_no)); }
+    let v: Vec<f32> = f[1..].iter().map(|t| r.parse(t)).collect::<Result<_, _>>()?;
+    let mut player = Player::new(v[0], v[1], v[2], v[3]);
+    player.health = v[4];
+    player.health_max = v[5];
+    player.shield = v[6];
+    player.shield_max = v[7];
+    player.stamina = v[8];
+    player.stamina_max = v[9];
+    player.binocular_timer = v[10];
+    player.step_timer = v[11];
+    player.radius = v[12];
+    player.pitch = v.get(13).copied().unwrap_or(0.0);
+    player.crouch = v.get(14).copied().unwrap_or(0.0);
+
+    let f = r.fields(3)?;
+    if f[0] != "inventory" { return Err(format!("linea {}: se esperaba 'inventory'", r.line_no)); }
+    let (count, active): (usize, usize) = (r.parse(f[1])?, r.parse(f[2])?);
+    for _ in 0..count {
+        let f = r.fields(2)?;
+        let item = item_from_id(f[0]).ok_or_else(|| format!("linea {}: objeto desconocido '{}'", r.line_no, f[0]))?;
+        player.inventory.add_many(item, r.parse(f[1])?);
+    }
+    player.inventory.set_active(active);
+
+    let count: usize = r.section("enemies")?;
+    let mut enemies = Vec::with_capacity(count);
+    for _ in 0..count {
+        let f = r.fields(9)?;
+        let spawn = (r.parse(f[0])?, r.parse(f[1])?);
+        let mut e = Enemy::new(spawn, block_size, r.parse(f[4])?, r.parse(f[5])?);
+        e.pos = Vector2::new(r.parse(f[2])?, r.parse(f[3])?);
+        e.radius = r.parse(f[6])?;
+        e.path_timer = r.parse(f[7])?;
+        e.attack_cooldown = r.parse(f[8])?;
+        enemies.push(e);
+    }
+
+    let count: usize = r.section("pickups")?;
+    let mut pickups = Vec::with_capacity(count);
+    for _ in 0..count {
+        let f = r.fields(4)?;
+        let item = item_from_id(f[2]).ok_or_else(|| format!("linea {}: objeto desconocido '{}'", r.line_no, f[2]))?;
+        let mut pk = Pickup::new((r.parse(f[0])?, r.parse(f[1])?), item);
+        pk.taken = f[3] == "1";
+        pickups.push(pk);
+    }
+
+    let count: usize = r.section("doors")?;
+    let mut doors = Vec::with_capacity(count);
+    for _ in 0..count {
+        let f = r.fields(9)?;
+        let state = door_state_from_id(r.parse(f[4])?).ok_or_else(|| format!("linea {}: estado de puerta invalido", r.line_no))?;
+        doors.push(Door {
+            cell: (r.parse(f[0])?, r.parse(f[1])?),
+            lock: r.parse(f[2])?,
+            unlocked: f[3] == "1",
+            state,
+            open: r.parse(f[5])?,
+            horizontal: f[6] == "1",
+            auto_close: if f[7] == "-" { None } else { Some(r.parse(f[7])?) },
+            timer: r.parse(f[8])?,
+        });
+    }
+
+    let world = GameWorld {
+        seed,
+        maze,
+        block_size,
+        player,
+        enemies,
+        pickups,
+        doors,
+        fog,
+        goal_unlocked,
+        interact_target: None,
+    };
+    validate(&world)?;
+    Ok(world)
+}
+
+fn validate(world: &GameWorld) -> Result<(), String> {
+    let rows = world.maze.len();
+    let cols = world.maze.first().map_or(0, |row| row.len());
+    if rows == 0 || cols == 0 || world.maze.iter().any(|row| row.len() != cols) {
+        return Err("el laberinto no es rectangular".to_string());
+    }
+    if world.fog.len() != rows || world.fog.iter().any(|row| row.len() != cols) {
+        return Err("la niebla no coincide con el laberinto".to_string());
+    }
+    if world.block_size == 0 {
+        return Err("block_size debe ser mayor que 0".to_string());
+    }
+    let inside = |cell: (usize, usize)| cell.0 < cols && cell.1 < rows;
+    let inside_pos = |pos: Vector2| {
+        pos.x >= 0.0 && pos.y >= 0.0 && inside(((pos.x as usize) / world.block_size, (pos.y as usize) / world.block_size))
+    };
+    if !inside_pos(world.player.pos) {
+        return Err("el jugador esta fuera del laberinto".to_string());
+    }
+    if world.enemies.iter().any(|e| !inside(e.spawn_cell) || !inside_pos(e.pos)) {
+        return Err("hay un enemigo fuera del laberinto".to_string());
+    }
+    if world.pickups.iter().any(|p| !inside(p.cell)) {
+        return Err("hay un objeto fuera del laberinto".to_string());
+    }
+    if world.doors.iter().any(|d| !inside(d.cell)) {
+        return Err("hay una puerta fuera del laberinto".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputFrame;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut world = GameWorld::new(77);
+        let frame = InputFrame { forward: 1.0, turn: 0.8, ..Default::default() };
+        for _ in 0..90 { world.step(&frame, 1.0 / 60.0); }
+        world.player.inventory.add(Item::Key(2));
+        world.pickups[0].taken = true;
+        world.doors[0].state = DoorState::Opening;
+        world.doors[0].open = 0.4;
+        world.player.pitch = 0.2;
+        world.player.crouch = 0.75;
+
+        let path = std::env::temp_dir().join(format!("mansion_save_test_{}.sav", std::process::id()));
+        save_world(&world, &path).unwrap();
+        let loaded = load_world(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded.seed, world.seed);
+        assert_eq!(loaded.maze, world.maze);
+        assert_eq!(loaded.fog, world.fog);
+        assert_eq!(loaded.player.pos, world.player.pos);
+        assert_eq!(loaded.player.a, world.player.a);
+        assert_eq!(loaded.player.stamina, world.player.stamina);
+        assert_eq!(loaded.player.pitch, 0.2);
+        assert_eq!(loaded.player.crouch, 0.75);
+        assert!(loaded.player.inventory.has_key(2));
+        assert!(loaded.pickups[0].taken);
+        assert_eq!(loaded.doors[0].state, DoorState::Opening);
+        assert_eq!(loaded.doors[0].open, 0.4);
+        let epos: Vec<_> = loaded.enemies.iter().map(|e| e.pos).collect();
+        let wpos: Vec<_> = world.enemies.iter().map(|e| e.pos).collect();
+        assert_eq!(epos, wpos);
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let path = std::env::temp_dir().join(format!("mansion_save_version_{}.sav", std::process::id()));
+        fs::write(&path, format!("{} {}\n", SAVE_MAGIC, SAVE_VERSION + 1)).unwrap();
+        let err = load_world(&path).err().unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(err.contains("no soportada"));
+    }
+
+    #[test]
+    fn loads_version_1_saves_standing_and_level() {
+        let mut world = GameWorld::new(11);
+        world.player.pitch = 0.3;
+        let path = std::env::temp_dir().join(format!("mansion_save_v1_{}.sav", std::process::id()));
+        save_world(&world, &path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let old: Vec<String> = text
+            .lines()
+            .map(|line| match line.split_whitespace().collect::<Vec<_>>() {
+                f if f.first() == Some(&SAVE_MAGIC) => format!("{} 1", SAVE_MAGIC),
+                f if f.first() == Some(&"player") => f[..PLAYER_FIELDS_V1].join(" "),
+                _ => line.to_string(),
+            })
+            .collect();
+        fs::write(&path, old.join("\n")).unwrap();
+        let loaded = load_world(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.player.pos, world.player.pos);
+        assert_eq!(loaded.player.pitch, 0.0);
+        assert_eq!(loaded.player.crouch, 0.0);
+    }
+
+    #[test]
+    fn rejects_inconsistent_worlds() {
+        let world = GameWorld::new(5);
+        let path = std::env::temp_dir().join(format!("mansion_save_broken_{}.sav", std::process::id()));
+        save_world(&world, &path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let broken = text.replacen(&world.maze[1].iter().collect::<String>(), "#", 1);
+        fs::write(&path, broken).unwrap();
+        let err = load_world(&path).err().unwrap();
+        let _ = fs::remove_file(&path);
+        assert!(err.contains("rectangular"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::audio::Bus;
+use crate::textures::TextureFilter;
+
+pub const RESOLUTIONS: [(i32, i32); 5] = [(1280, 720), (1366, 768), (1600, 900), (1820, 980), (1920, 1080)];
+const APP_DIR: &str = "mansion_embrujada";
+const SETTINGS_FILE: &str = "settings.cfg";
+const KEYMAP_FILE: &str = "keymap.cfg";
+const SAVE_FILE: &str = "partida.sav";
+const REPLAY_FILE: &str = "ultima_partida.replay";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub ambient_volume: f32,
+    pub muted: bool,
+    pub window_w: i32,
+    pub window_h: i32,
+    pub fov_deg: f32,
+    pub render_scale: usize,
+    pub texture_filter: TextureFilter,
+    pub mouse_sensitivity: f32,
+    pub invert_mouse: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            master_volume: 1.0,
+            music_volume: 0.45,
+            sfx_volume: 0.4,
+            ambient_volume: 0.6,
+            muted: false,
+            window_w: 1820,
+            window_h: 980,
+            fov_deg: 60.0,
+            render_scale: 3,
+            texture_filter: TextureFilter::Trilinear,
+            mouse_sensitivity: 0.003,
+            invert_mouse: false,
+        }
+    }
+}
+
+pub fn config_dir() -> PathBuf {
+    let env = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let base = if cfg!(windows) {
+        env("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|h| h.join("Library").join("Application Support"))
+    } else {
+        env("XDG_CONFIG_HOME").or_else(|| env("HOME").map(|h| h.join(".config")))
+    };
+    base.unwrap_or_else(|| PathBuf::from(".")).join(APP_DIR)
+}
+
+pub fn settings_path() -> PathBuf {
+    config_dir().join(SETTINGS_FILE)
+}
+
+pub fn keymap_path() -> PathBuf {
+    config_dir().join(KEYMAP_FILE)
+}
+
+pub fn save_path() -> PathBuf {
+    config_dir().join(SAVE_FILE)
+}
+
+pub fn replay_path() -> PathBuf {
+    config_dir().join(REPLAY_FILE)
+}
+
+impl Settings {
+    pub fn fov(&self) -> f32 {
+        self.fov_deg.to_radians()
+    }
+
+    pub fn volume(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Master => self.master_volume,
+            Bus::Music => self.music_volume,
+            Bus::Sfx => self.sfx_volume,
+            Bus::Ambient => self.ambient_volume,
+        }
+    }
+
+    pub fn volume_mut(&mut self, bus: Bus) -> &mut f32 {
+        match bus {
+            Bus::Master => &mut self.master_volume,
+            Bus::Music => &mut self.music_volume,
+            Bus::Sfx => &mut self.sfx_volume,
+            Bus::Ambient => &mut self.ambient_volume,
+        }
+    }
+
+    pub fn resolution_index(&self) -> usize {
+        RESOLUTIONS.iter().position(|&r| r == (self.window_w, self.window_h)).unwrap_or(RESOLUTIONS.len() - 1)
+    }
+
+    pub fn set_resolution(&mut self, index: usize) {
+        let (w, h) = RESOLUTIONS[index.min(RESOLUTIONS.len() - 1)];
+        self.window_w = w;
+        self.window_h = h;
+    }
+
+    fn clamp(&mut self) {
+        for bus in Bus::ALL {
+            let v = self.volume_mut(bus);
+            *v = v.clamp(0.0, 1.0);
+        }
+        self.window_w = self.window_w.clamp(640, 3840);
+        self.window_h = self.window_h.clamp(480, 2160);
+        self.fov_deg = self.fov_deg.clamp(45.0, 100.0);
+        self.render_scale = self.render_scale.clamp(1, 6);
+        self.mouse_sensitivity = self.mouse_sensitivity.clamp(0.0005, 0.01);
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut s = Settings::default();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let Some((key, value)) = line.split_once('=') else {
+                eprintln!("Ajustes: linea {} invalida", n + 1);
+                continue;
+            };
+            let value = value.trim();
+            let ok = match key.trim() {
+                "master_volume" => value.parse().map(|v| s.master_volume = v).is_ok(),
+                "music_volume" => value.parse().map(|v| s.music_volume = v).is_ok(),
+                "sfx_volume" => value.parse().map(|v| s.sfx_volume = v).is_ok(),
+                "ambient_volume" => value.parse().map(|v| s.ambient_volume = v).is_ok(),
+                "muted" => value.parse().map(|v| s.muted = v).is_ok(),
+                "window_width" => value.parse().map(|v| s.window_w = v).is_ok(),
+                "window_height" => value.parse().map(|v| s.window_h = v).is_ok(),
+                "fov" => value.parse().map(|v| s.fov_deg = v).is_ok(),
+                "render_scale" => value.parse().map(|v| s.render_scale = v).is_ok(),
+                "texture_filter" => TextureFilter::from_key(value).map(|v| s.texture_filter = v).is_some(),
+                "mouse_sensitivity" => value.parse().map(|v| s.mouse_sensitivity = v).is_ok(),
+                "invert_mouse" => value.parse().map(|v| s.invert_mouse = v).is_ok(),
+                other => {
+                    eprintln!("Ajustes: clave desconocida '{}'", other);
+                    true
+                }
+            };
+            if !ok { eprintln!("Ajustes: valor invalido '{}' en la linea {}", value, n + 1); }
+        }
+        s.clamp();
+        s
+    }
+
+    pub fn load(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(text) => Settings::parse(&text),
+            Err(_) => Settings::default(),
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        format!(
+            "master_volume = {}\nmusic_volume = {}\nsfx_volume = {}\nambient_volume = {}\nmuted = {}\nwindow_width = {}\nwindow_height = {}\nfov = {}\nrender_scale = {}\ntexture_filter = {}\nmouse_sensitivity = {}\ninvert_mouse = {}\n",
+            self.master_volume, self.music_volume, self.sfx_volume, self.ambient_volume, self.muted, self.window_w, self.window_h, self.fov_deg,
+            self.render_scale, self.texture_filter.key(), self.mouse_sensitivity, self.invert_mouse
+        )
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(dir) = path.parent() { fs::create_dir_all(dir)?; }
+        fs::write(path, self.to_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_round_trip() {
+        let mut s = Settings { music_volume: 0.2, ambient_volume: 0.1, muted: true, fov_deg: 75.0, render_scale: 2, texture_filter: TextureFilter::Nearest, invert_mouse: true, ..Default::default() };
+        s.set_resolution(0);
+        assert_eq!(Settings::parse(&s.to_text()), s);
+    }
+
+    #[test]
+    fn bad_values_fall_back_and_clamp() {
+        let s = Settings::parse("music_volume = fuerte\nfov = 300\nrender_scale = 0\nbasura\n");
+        assert_eq!(s.music_volume, Settings::default().music_volume);
+        assert_eq!(s.fov_deg, 100.0);
+        assert_eq!(s.render_scale, 1);
+    }
+}
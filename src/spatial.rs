@@ -0,0 +1,101 @@
+use raylib::prelude::Vector2;
+use std::f32::consts::PI;
+use crate::caster::cast_ray;
+use crate::door::Door;
+use crate::maze::Maze;
+use crate::player::Player;
+use crate::world::GameWorld;
+
+pub const HEARING_BLOCKS: f32 = 8.0;
+const OCCLUDED_GAIN: f32 = 0.45;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpatialParams {
+    pub gain: f32,
+    pub pan: f32,
+    pub muffled: bool,
+}
+
+impl SpatialParams {
+    pub fn ear_gains(&self) -> (f32, f32) {
+        let theta = (self.pan.clamp(-1.0, 1.0) + 1.0) * PI / 4.0;
+        (theta.cos() * self.gain, theta.sin() * self.gain)
+    }
+}
+
+pub fn is_occluded(maze: &Maze, doors: &[Door], player: &Player, source: Vector2, block_size: usize) -> bool {
+    let (dx, dy) = (source.x - player.pos.x, source.y - player.pos.y);
+    let dist = (dx*dx + dy*dy).sqrt();
+    if dist < 1e-3 { return false; }
+    cast_ray(maze, doors, player, dy.atan2(dx), block_size).distance < dist
+}
+
+pub fn spatialize(player: &Player, source: Vector2, max_dist: f32, occluded: bool) -> SpatialParams {
+    let (dx, dy) = (source.x - player.pos.x, source.y - player.pos.y);
+    let dist = (dx*dx + dy*dy).sqrt();
+    let t = (dist / max_dist).clamp(0.0, 1.0);
+    let mut gain = (1.0 - t) * (1.0 - t);
+    if occluded { gain *= OCCLUDED_GAIN; }
+    let pan = if dist < 1e-3 { 0.0 } else { (dy.atan2(dx) - player.a).sin() };
+    SpatialParams { gain, pan, muffled: occluded }
+}
+
+pub fn enemy_emitters(world: &GameWorld) -> Vec<(usize, SpatialParams)> {
+    let max_dist = world.block_size as f32 * HEARING_BLOCKS;
+    world
+        .enemies
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| {
+            let (dx, dy) = (e.pos.x - world.player.pos.x, e.pos.y - world.player.pos.y);
+            dx*dx + dy*dy < max_dist * max_dist
+        })
+        .map(|(i, e)| {
+            let occluded = is_occluded(&world.maze, &world.doors, &world.player, e.pos, world.block_size);
+            (i, spatialize(&world.player, e.pos, max_dist, occluded))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BS: usize = 64;
+
+    fn maze() -> Maze {
+        ["+---+", "|   |", "| | |", "|   |", "+---+"].iter().map(|r| r.chars().collect()).collect()
+    }
+
+    fn at(cell: (usize, usize)) -> Vector2 {
+        Vector2::new((cell.0 * BS) as f32 + 32.0, (cell.1 * BS) as f32 + 32.0)
+    }
+
+    #[test]
+    fn pans_toward_the_side_of_the_source() {
+        let p = Player::new(at((1, 1)).x, at((1, 1)).y, 0.0, PI / 3.0);
+        let right = spatialize(&p, at((1, 3)), 512.0, false);
+        let left = spatialize(&p, Vector2::new(p.pos.x, p.pos.y - 64.0), 512.0, false);
+        assert!(right.pan > 0.9 && left.pan < -0.9);
+        let (l, r) = right.ear_gains();
+        assert!(r > l);
+    }
+
+    #[test]
+    fn farther_sources_are_quieter() {
+        let p = Player::new(at((1, 1)).x, at((1, 1)).y, 0.0, PI / 3.0);
+        let near = spatialize(&p, at((2, 1)), 512.0, false);
+        let far = spatialize(&p, at((3, 1)), 512.0, false);
+        assert!(near.gain > far.gain && far.gain > 0.0);
+        assert_eq!(spatialize(&p, Vector2::new(p.pos.x + 600.0, p.pos.y), 512.0, false).gain, 0.0);
+    }
+
+    #[test]
+    fn walls_between_listener_and_source_occlude() {
+        let p = Player::new(at((1, 2)).x, at((1, 2)).y, 0.0, PI / 3.0);
+        assert!(is_occluded(&maze(), &[], &p, at((3, 2)), BS));
+        assert!(!is_occluded(&maze(), &[], &p, at((1, 3)), BS));
+        let params = spatialize(&p, at((3, 2)), 512.0, true);
+        assert!(params.muffled && params.gain < spatialize(&p, at((3, 2)), 512.0, false).gain);
+    }
+}
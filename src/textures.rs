@@ -1,99 +1,251 @@
 use raylib::prelude::*;
 use std::collections::HashMap;
-use image::{GenericImageView, DynamicImage, imageops::FilterType};
+use image::{GenericImageView, DynamicImage, RgbaImage, imageops::FilterType};
+use crate::assets::{AssetKind, Assets};
 
-struct TextureEntry {
-    data: Vec<u8>, 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+    Trilinear,
+}
+
+impl TextureFilter {
+    pub const ALL: [TextureFilter; 3] = [TextureFilter::Nearest, TextureFilter::Bilinear, TextureFilter::Trilinear];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TextureFilter::Nearest => "Vecino cercano",
+            TextureFilter::Bilinear => "Bilineal",
+            TextureFilter::Trilinear => "Trilineal",
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            TextureFilter::Nearest => "nearest",
+            TextureFilter::Bilinear => "bilinear",
+            TextureFilter::Trilinear => "trilinear",
+        }
+    }
+
+    pub fn from_key(text: &str) -> Option<Self> {
+        TextureFilter::ALL.into_iter().find(|f| f.key() == text)
+    }
+
+    pub fn cycle(self, step: i32) -> Self {
+        let n = TextureFilter::ALL.len() as i32;
+        let i = TextureFilter::ALL.iter().position(|&f| f == self).unwrap_or(0) as i32;
+        TextureFilter::ALL[(i + step).rem_euclid(n) as usize]
+    }
+}
+
+pub fn placeholder_texture_image() -> Image {
+    Image::gen_image_checked(64, 64, 8, 8, Color::MAGENTA, Color::BLACK)
+}
+
+struct MipLevel {
+    data: Vec<u8>,
     width: usize,
     height: usize,
-    texture: Texture2D,
 }
 
+impl MipLevel {
+    fn texel(&self, x: usize, y: usize) -> [f32; 4] {
+        let i = (y * self.width + x) * 4;
+        [self.data[i] as f32, self.data[i + 1] as f32, self.data[i + 2] as f32, self.data[i + 3] as f32]
+    }
+
+    fn nearest(&self, u: f32, v: f32) -> [f32; 4] {
+        let x = ((u * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+        self.texel(x, y)
+    }
+
+    fn bilinear(&self, u: f32, v: f32) -> [f32; 4] {
+        let (x, y) = (u * self.width as f32 - 0.5, v * self.height as f32 - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let wrap = |i: f32, n: usize| (i as isize).rem_euclid(n as isize) as usize;
+        let (xa, xb) = (wrap(x0, self.width), wrap(x0 + 1.0, self.width));
+        let (ya, yb) = (wrap(y0, self.height), wrap(y0 + 1.0, self.height));
+        let top = lerp4(self.texel(xa, ya), self.texel(xb, ya), x - x0);
+        let bottom = lerp4(self.texel(xa, yb), self.texel(xb, yb), x - x0);
+        lerp4(top, bottom, y - y0)
+    }
+}
+
+fn lerp4(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+fn build_mips(base: RgbaImage) -> Vec<MipLevel> {
+    let mut levels = Vec::new();
+    let mut img = base;
+    loop {
+        let (w, h) = img.dimensions();
+        let next = (w > 1 || h > 1).then(|| image::imageops::resize(&img, (w / 2).max(1), (h / 2).max(1), FilterType::Triangle));
+        levels.push(MipLevel { data: img.into_raw(), width: w as usize, height: h as usize });
+        match next {
+            Some(n) => img = n,
+            None => return levels,
+        }
+    }
+}
+
+fn sample_mips(levels: &[MipLevel], filter: TextureFilter, u: f32, v: f32, texels_per_px: f32) -> [f32; 4] {
+    let lod = texels_per_px.max(1.0).log2().min((levels.len() - 1) as f32);
+    match filter {
+        TextureFilter::Nearest => levels[lod.round() as usize].nearest(u, v),
+        TextureFilter::Bilinear => levels[lod.round() as usize].bilinear(u, v),
+        TextureFilter::Trilinear => {
+            let lo = lod.floor() as usize;
+            let hi = (lo + 1).min(levels.len() - 1);
+            lerp4(levels[lo].bilinear(u, v), levels[hi].bilinear(u, v), lod.fract())
+        }
+    }
+}
+
+struct TextureEntry {
+    mips: Vec<MipLevel>,
+}
+
+// Each char keeps the mip chain of its own region so distant walls never blend in neighbouring atlas cells.
 pub struct TextureManager {
     entries: HashMap<char, TextureEntry>,
+    sheets: HashMap<String, Texture2D>,
+    regions: HashMap<char, (String, Rectangle)>,
+    pub filter: TextureFilter,
 }
 
 impl TextureManager {
-    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
-        let mut entries = HashMap::new();
-
-        let texture_files = vec![
-            ('+', "assets/textura_pared.png"),
-            ('-', "assets/textura_pared.png"),
-            ('|', "assets/textura_pared.png"),
-            ('D', "assets/door.png"),
-            ('g', "assets/textura_pared.png"),
-            ('#', "assets/textura_pared.png"),
-        ];
-
-        for (ch, path) in texture_files {
-            let dynimg = match image::open(path) {
-                Ok(i) => i,
-                Err(e) => panic!("TextureManager: no pude cargar {}: {:?}", path, e),
-            };
-
-            let (w0, h0) = dynimg.dimensions();
-            let max_dim = 512u32;
-            let img_rgba: DynamicImage = if w0 > max_dim || h0 > max_dim {
-                let scale = (max_dim as f32) / (w0 as f32).max(h0 as f32);
-                let new_w = ((w0 as f32) * scale).max(1.0) as u32;
-                let new_h = ((h0 as f32) * scale).max(1.0) as u32;
-                let small = image::imageops::resize(&dynimg.to_rgba8(), new_w, new_h, FilterType::Triangle);
-                DynamicImage::ImageRgba8(small)
-            } else {
-                dynimg.to_rgba8().into()
-            };
-
-            let rgba = img_rgba.to_rgba8();
-            let (w, h) = rgba.dimensions();
-            let buf = rgba.into_raw();
-
-            let texture = match rl.load_texture(thread, path) {
-                Ok(t) => t,
-                Err(_) => {
-                    rl.load_texture_from_image(thread, &Image::gen_image_color(1, 1, Color::WHITE))
-                        .expect("failed fallback texture")
-                }
-            };
-
-            entries.insert(ch, TextureEntry {
-                data: buf,
-                width: w as usize,
-                height: h as usize,
-                texture,
-            });
+    pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread, assets: &Assets) -> Self {
+        let mut manager = TextureManager { entries: HashMap::new(), sheets: HashMap::new(), regions: HashMap::new(), filter: TextureFilter::Trilinear };
+        for id in assets.ids(AssetKind::Wall) {
+            manager.load(rl, thread, assets, id, false);
         }
+        manager
+    }
 
-        TextureManager { entries }
+    pub fn reload(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, assets: &Assets, id: &str) {
+        self.load(rl, thread, assets, id, true);
     }
 
-    pub fn sample_char(&self, ch: char, u: f32, v: f32) -> Color {
-        if let Some(entry) = self.entries.get(&ch) {
-            let uu = (u.fract() + 1.0).fract();
-            let vv = (v.fract() + 1.0).fract();
+    fn load(&mut self, rl: &mut RaylibHandle, thread: &RaylibThread, assets: &Assets, id: &str, upload: bool) {
+        let Some(ch) = id.chars().next() else { return; };
+        let sheet_img = assets.image(AssetKind::Wall, id);
+        let (sheet_w, sheet_h) = sheet_img.dimensions();
+        let [x, y, w, h] = assets.rect(AssetKind::Wall, id).unwrap_or([0, 0, sheet_w, sheet_h]);
+        let dynimg = DynamicImage::ImageRgba8(tile_image(sheet_img, [x, y, w, h]));
+
+        let (w0, h0) = dynimg.dimensions();
+        let max_dim = 512u32;
+        let img_rgba: DynamicImage = if w0 > max_dim || h0 > max_dim {
+            let scale = (max_dim as f32) / (w0 as f32).max(h0 as f32);
+            let new_w = ((w0 as f32) * scale).max(1.0) as u32;
+            let new_h = ((h0 as f32) * scale).max(1.0) as u32;
+            let small = image::imageops::resize(&dynimg.to_rgba8(), new_w, new_h, FilterType::Triangle);
+            DynamicImage::ImageRgba8(small)
+        } else {
+            dynimg
+        };
 
-            let tx = ((uu * (entry.width.saturating_sub(1) as f32)).round() as usize).min(entry.width.saturating_sub(1));
-            let ty = ((vv * (entry.height.saturating_sub(1) as f32)).round() as usize).min(entry.height.saturating_sub(1));
+        let mips = build_mips(img_rgba.to_rgba8());
 
-            let idx = (ty * entry.width + tx).saturating_mul(4);
-            if idx + 3 < entry.data.len() {
-                let r = entry.data[idx];
-                let g = entry.data[idx + 1];
-                let b = entry.data[idx + 2];
-                let a = entry.data[idx + 3];
-                return Color::new(r, g, b, a);
-            } else {
-                return Color::WHITE;
-            }
+        let asset = assets.get(AssetKind::Wall, id);
+        let sheet = asset.map_or_else(|| format!("?{}", id), |a| a.entry.path.clone());
+        if upload || !self.sheets.contains_key(&sheet) {
+            let image = asset
+                .and_then(|a| Image::load_image_from_mem(&a.entry.extension(), &a.bytes).ok())
+                .unwrap_or_else(placeholder_texture_image);
+            let texture = rl.load_texture_from_image(thread, &image).expect("TextureManager: no se pudo subir la textura");
+            self.sheets.insert(sheet.clone(), texture);
         }
-        Color::WHITE
+
+        self.regions.insert(ch, (sheet, Rectangle::new(x as f32, y as f32, w as f32, h as f32)));
+        self.entries.insert(ch, TextureEntry { mips });
+    }
+
+    pub fn sample_char(&self, ch: char, u: f32, v: f32, texels_per_px: f32) -> Color {
+        let Some(entry) = self.entries.get(&ch) else { return Color::WHITE; };
+        let uu = (u.fract() + 1.0).fract();
+        let vv = (v.fract() + 1.0).fract();
+        let [r, g, b, a] = sample_mips(&entry.mips, self.filter, uu, vv, texels_per_px).map(|c| c.round() as u8);
+        Color::new(r, g, b, a)
     }
 
-    pub fn get_texture(&self, ch: char) -> Option<&Texture2D> {
-        self.entries.get(&ch).map(|e| &e.texture)
+    pub fn get_texture(&self, ch: char) -> Option<(&Texture2D, Rectangle)> {
+        let (sheet, rect) = self.regions.get(&ch)?;
+        Some((self.sheets.get(sheet)?, *rect))
     }
 
     pub fn tex_size(&self, ch: char) -> Option<(usize,usize)> {
-        self.entries.get(&ch).map(|e| (e.width, e.height))
+        self.entries.get(&ch).map(|e| (e.mips[0].width, e.mips[0].height))
+    }
+}
+
+fn tile_image(sheet: RgbaImage, [x, y, w, h]: [u32; 4]) -> RgbaImage {
+    if (x, y, w, h) == (0, 0, sheet.width(), sheet.height()) {
+        return sheet;
+    }
+    image::imageops::crop_imm(&sheet, x, y, w, h).to_image()
+}
+
+pub fn asset_texture(rl: &mut RaylibHandle, thread: &RaylibThread, assets: &Assets, kind: AssetKind, id: &str) -> Option<Texture2D> {
+    let asset = assets.get(kind, id)?;
+    let image = Image::load_image_from_mem(&asset.entry.extension(), &asset.bytes).ok()?;
+    rl.load_texture_from_image(thread, &image).ok()
+}
+
+pub fn sprite_texture(rl: &mut RaylibHandle, thread: &RaylibThread, assets: &Assets, id: &str) -> Texture2D {
+    asset_texture(rl, thread, assets, AssetKind::Sprite, id)
+        .or_else(|| rl.load_texture_from_image(thread, &placeholder_texture_image()).ok())
+        .expect("no se pudo crear la textura de reemplazo")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn checker(size: u32) -> Vec<MipLevel> {
+        build_mips(RgbaImage::from_fn(size, size, |x, y| if (x + y) % 2 == 0 { Rgba([255, 255, 255, 255]) } else { Rgba([0, 0, 0, 255]) }))
+    }
+
+    #[test]
+    fn mip_chain_halves_down_to_one_texel() {
+        let mips = build_mips(RgbaImage::new(8, 4));
+        let sizes: Vec<_> = mips.iter().map(|m| (m.width, m.height)).collect();
+        assert_eq!(sizes, vec![(8, 4), (4, 2), (2, 1), (1, 1)]);
+    }
+
+    #[test]
+    fn distant_walls_sample_averaged_levels() {
+        let mips = checker(16);
+        let near = sample_mips(&mips, TextureFilter::Nearest, 0.0, 0.0, 1.0);
+        let far = sample_mips(&mips, TextureFilter::Nearest, 0.0, 0.0, 16.0);
+        assert_eq!(near[0], 255.0);
+        assert!((far[0] - 127.5).abs() < 20.0);
+    }
+
+    #[test]
+    fn atlas_regions_keep_their_own_mips() {
+        let sheet = RgbaImage::from_fn(32, 16, |x, _| if x < 16 { Rgba([255, 0, 0, 255]) } else { Rgba([0, 0, 255, 255]) });
+        let mips = build_mips(tile_image(sheet, [16, 0, 16, 16]));
+        assert_eq!((mips[0].width, mips[0].height), (16, 16));
+        for tpp in [1.0, 4.0, 64.0] {
+            let [r, _, b, _] = sample_mips(&mips, TextureFilter::Trilinear, 0.0, 0.5, tpp);
+            assert_eq!((r, b), (0.0, 255.0));
+        }
+    }
+
+    #[test]
+    fn filters_blend_neighbouring_texels() {
+        let mips = checker(16);
+        let between = 1.0 / 16.0;
+        assert_eq!(sample_mips(&mips, TextureFilter::Nearest, between, 0.5 / 16.0, 1.0)[0], 0.0);
+        assert!((sample_mips(&mips, TextureFilter::Bilinear, between, 0.5 / 16.0, 1.0)[0] - 127.5).abs() < 1.0);
+        let tri = sample_mips(&mips, TextureFilter::Trilinear, 0.5 / 16.0, 0.5 / 16.0, 1.5)[0];
+        let (l0, l1) = (sample_mips(&mips, TextureFilter::Bilinear, 0.5 / 16.0, 0.5 / 16.0, 1.0)[0], sample_mips(&mips, TextureFilter::Bilinear, 0.5 / 16.0, 0.5 / 16.0, 2.0)[0]);
+        assert!(tri < l0.max(l1) && tri > l0.min(l1));
     }
 }
@@ -0,0 +1,460 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::f32::consts::PI;
+use crate::maze::{Maze, LOW_WALLS, SEE_THROUGH};
+use crate::player::Player;
+use crate::enemy::Enemy;
+use crate::pickup::Pickup;
+use crate::inventory::Item;
+use crate::door::{self, Door, GOAL_LOCK};
+use crate::generator::{generate_maze_text, place_locks, place_pillars, place_wall_variants};
+use crate::collision::{overlapped_cells, PLAYER_RADIUS};
+use crate::input::{apply_input, InputFrame};
+use crate::interact::{find_target, interact, Target};
+use crate::events::GameEvent;
+
+const SEE_THROUGH_WALLS: usize = 8;
+const LOW_WALLS_COUNT: usize = 8;
+const PILLARS: usize = 6;
+const DECORATION_SALT: u64 = 0x7E1A_4A5A;
+const LOGICAL_VIEW_W: usize = 1820;
+const LOGICAL_VIEW_H: usize = 980;
+
+pub struct GameWorld {
+    pub seed: u64,
+    pub maze: Maze,
+    pub block_size: usize,
+    pub player: Player,
+    pub enemies: Vec<Enemy>,
+    pub pickups: Vec<Pickup>,
+    pub doors: Vec<Door>,
+    pub fog: Vec<Vec<bool>>,
+    pub goal_unlocked: bool,
+    pub interact_target: Option<Target>,
+}
+
+impl GameWorld {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let w = 12usize; let h = 10usize;
+        let mut raw = generate_maze_text(w, h, &mut rng);
+        let mut deco_rng = StdRng::seed_from_u64(seed ^ DECORATION_SALT);
+        place_wall_variants(&mut raw, SEE_THROUGH_WALLS, &SEE_THROUGH, &mut deco_rng);
+        place_wall_variants(&mut raw, LOW_WALLS_COUNT, &LOW_WALLS, &mut deco_rng);
+        place_pillars(&mut raw, PILLARS, &mut deco_rng);
+        let mut maze = expand_maze(&raw, 2);
+
+        let block_size = {
+            let bw = LOGICAL_VIEW_W / maze[0].len();
+            let bh = LOGICAL_VIEW_H / maze.len();
+            std::cmp::max(6, std::cmp::min(bw, bh))
+        };
+
+        let mut fog = vec![vec![false; maze[0].len()]; maze.len()];
+
+        let mut orig_px = 1usize; let mut orig_py = 1usize;
+        'find_p: for (r,row) in maze.iter().enumerate() {
+            for (c,&ch) in row.iter().enumerate() {
+                if ch == 'p' { orig_px = c; orig_py = r; break 'find_p; }
+            }
+        }
+
+        let rows = maze.len();
+        let cols = maze[0].len();
+        let mut reachable = vec![vec![false; cols]; rows];
+        use std::collections::VecDeque;
+        let mut q: VecDeque<(usize,usize)> = VecDeque::new();
+        for r in 0..rows {
+            for c in 0..cols {
+                if r==0 || r==rows-1 || c==0 || c==cols-1 {
+                    if maze[r][c] == ' ' {
+                        reachable[r][c] = true;
+                        q.push_back((r,c));
+                    }
+                }
+            }
+        }
+        while let Some((r,c)) = q.pop_front() {
+            let neigh = [(1isize,0),( -1,0),(0,1),(0,-1)];
+            for (dy,dx) in neigh.iter() {
+                let nr = r as isize + dy;
+                let nc = c as isize + dx;
+                if nr >= 0 && nc >= 0 && (nr as usize) < rows && (nc as usize) < cols {
+                    let (nr, nc) = (nr as usize, nc as usize);
+                    if !reachable[nr][nc] && maze[nr][nc] == ' ' {
+                        reachable[nr][nc] = true;
+                        q.push_back((nr,nc));
+                    }
+                }
+            }
+        }
+
+        let mut spawn_x = orig_px;
+        let mut spawn_y = orig_py;
+        if !reachable[orig_py][orig_px] {
+            let mut best: Option<(usize,usize)> = None;
+            let mut bestd = usize::MAX;
+            for r in 0..rows {
+                for c in 0..cols {
+                    if reachable[r][c] {
+                        let d = ((r as isize - orig_py as isize).abs() + (c as isize - orig_px as isize).abs()) as usize;
+                        if d < bestd {
+                            bestd = d;
+                            best = Some((c,r));
+                        }
+                    }
+                }
+            }
+            if let Some((cx,cy)) = best {
+                spawn_x = cx; spawn_y = cy;
+            } else {
+                'fallback_find: for r in 0..rows {
+                    for c in 0..cols {
+                        if maze[r][c] == ' ' { spawn_x = c; spawn_y = r; break 'fallback_find; }
+                    }
+                }
+            }
+        }
+
+        maze[spawn_y][spawn_x] = ' ';
+
+        let goal_x = if cols >= 3 { cols - 2 } else { cols.saturating_sub(1) };
+        let goal_y = if rows >= 3 { rows - 2 } else { rows.saturating_sub(1) };
+        for r in 0..rows { for c in 0..cols { if maze[r][c] == 'g' { maze[r][c] = ' '; } } }
+        maze[goal_y][goal_x] = 'g';
+        let mut doors: Vec<Door> = Vec::new();
+        let neigh = [(1isize,0isize),(-1,0),(0,1),(0,-1)];
+        for &(dx,dy) in neigh.iter() {
+            let nx = goal_x as isize + dx;
+            let ny = goal_y as isize + dy;
+            if nx >= 0 && ny >= 0 && (ny as usize) < rows && (nx as usize) < cols {
+                let (nxu, nyu) = (nx as usize, ny as usize);
+                if maze[nyu][nxu] != 'g' {
+                    maze[nyu][nxu] = 'D';
+                    doors.push(Door::new(&maze, (nxu, nyu), GOAL_LOCK, None));
+                }
+            }
+        }
+        let goal_doors: Vec<(usize,usize)> = doors.iter().map(|d| d.cell).collect();
+        let layout = place_locks(&mut maze, 2, (spawn_x, spawn_y), (goal_x, goal_y), &goal_doors, 3, &mut rng);
+        for &(cell, lock) in layout.doors.iter() { doors.push(Door::new(&maze, cell, lock, Some(5.0))); }
+
+        let cx = (spawn_x * block_size) as f32 + (block_size as f32)/2.0;
+        let cy = (spawn_y * block_size) as f32 + (block_size as f32)/2.0;
+        let mut pl = Player::new(cx, cy, 0.0, PI/3.0);
+        pl.radius = block_size as f32 * PLAYER_RADIUS;
+        let player = pl;
+
+        let mut enemies: Vec<Enemy> = Vec::new();
+        let mut pickups: Vec<Pickup> = Vec::new();
+        let mut spawn1 = (cols.saturating_sub(3), 1usize);
+        'outer1: for r in 1..rows-1 { for c in (1..cols-1).rev() { if maze[r][c] == ' ' { spawn1=(c,r); break 'outer1; } } }
+        let mut spawn2 = (1usize, rows.saturating_sub(3));
+        'outer2: for r in (1..rows-1).rev() { for c in 1..cols-1 { if maze[r][c] == ' ' { spawn2=(c,r); break 'outer2; } } }
+        enemies.push(Enemy::new(spawn1, block_size, 0, 18.0));
+        enemies.push(Enemy::new(spawn2, block_size, 0, 18.0));
+
+        let mut placed = 0; let want_medkits = 6usize;
+        while placed < want_medkits {
+            let r = rng.gen_range(1..rows-1);
+            let c = rng.gen_range(1..cols-1);
+            if maze[r][c] == ' ' {
+                if !pickups.iter().any(|p| p.cell == (c,r)) {
+                    pickups.push(Pickup::new((c,r), Item::Medkit));
+                    placed += 1;
+                }
+            }
+        }
+
+        for &(cell, id) in layout.keys.iter() { pickups.push(Pickup::new(cell, Item::Key(id))); }
+
+        let mut placed_bin=false;
+        for _ in 0..300 {
+            let r = rng.gen_range(1..rows-1); let c = rng.gen_range(1..cols-1);
+            if maze[r][c] == ' ' { pickups.push(Pickup::new((c,r), Item::Binoculars)); placed_bin=true; break; }
+        }
+        if !placed_bin { pickups.push(Pickup::new((cols/2+1, rows/2), Item::Binoculars)); }
+
+        reveal_fog(&mut fog, spawn_x, spawn_y, &maze, 2);
+
+        GameWorld {
+            seed,
+            maze,
+            block_size,
+            player,
+            enemies,
+            pickups,
+            doors,
+            fog,
+            goal_unlocked: false,
+            interact_target: None,
+        }
+
+    }
+
+    pub fn nearest_enemy_path(&self) -> Option<usize> {
+        self.enemies.iter().filter(|e| !e.path.is_empty()).map(|e| e.path.len() - 1).min()
+    }
+
+    pub fn step(&mut self, input: &InputFrame, dt: f32) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        let block_size = self.block_size;
+        let pl = &mut self.player;
+
+        apply_input(pl, &self.maze, &self.doors, block_size, dt, input, &mut events);
+
+        self.interact_target = find_target(&self.maze, &self.doors, &self.pickups, pl, block_size);
+        if input.use_target && let Some(target) = self.interact_target {
+            if let Some(event) = interact(target, &self.maze, &mut self.doors, &mut self.pickups, pl, &mut self.goal_unlocked) {
+                events.push(event);
+            }
+            self.interact_target = find_target(&self.maze, &self.doors, &self.pickups, pl, block_size);
+        }
+
+        pl.update_timers(dt);
+
+        let pr = (pl.pos.y as usize) / block_size;
+        let pc = (pl.pos.x as usize) / block_size;
+        reveal_fog(&mut self.fog, pc, pr, &self.maze, 2);
+
+        let mut occupied = overlapped_cells(pl.pos, pl.radius, block_size);
+        for e in self.enemies.iter() { occupied.extend(overlapped_cells(e.pos, e.radius, block_size)); }
+        door::update_doors(&mut self.doors, dt, &occupied);
+
+        for e in self.enemies.iter_mut() {
+            let attacked = e.update(&self.maze, &self.doors, block_size, &pl.pos, dt);
+            if attacked {
+                pl.apply_damage(50.0);
+                events.push(GameEvent::Hurt(50.0));
+            }
+        }
+
+        if pl.health <= 0.0 {
+            events.push(GameEvent::Death);
+            return events;
+        }
+
+        let i = (pl.pos.x as usize) / block_size;
+        let j = (pl.pos.y as usize) / block_size;
+        if j < self.maze.len() && i < self.maze[j].len() && self.maze[j][i] == 'g' {
+            if pl.inventory.has_key(GOAL_LOCK) || self.goal_unlocked {
+                events.push(GameEvent::Victory);
+            } else {
+                events.push(GameEvent::GoalLocked);
+            }
+        }
+
+        events
+    }
+}
+
+pub fn expand_maze(maze: &Maze, factor: usize) -> Maze {
+    let rows = maze.len();
+    let cols = maze[0].len();
+    let mut out: Maze = Vec::with_capacity(rows * factor);
+    for r in 0..rows {
+        let mut new_rows: Vec<Vec<char>> = vec![Vec::with_capacity(cols * factor); factor];
+        for c in 0..cols {
+            let ch = maze[r][c];
+            for fr in 0..factor {
+                for _fc in 0..factor {
+                    new_rows[fr].push(ch);
+                }
+            }
+        }
+        for nr in new_rows.into_iter() { out.push(nr); }
+    }
+    out
+}
+
+pub fn reveal_fog(fog: &mut Vec<Vec<bool>>, cx: usize, cy: usize, maze: &Maze, r: i32) {
+    let rows = maze.len() as i32;
+    let cols = maze[0].len() as i32;
+    let cr = cy as i32;
+    let cc = cx as i32;
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let ny = cr + dy;
+            let nx = cc + dx;
+            if nx >= 0 && ny >= 0 && nx < cols && ny < rows {
+                let dist2 = dx*dx + dy*dy;
+                if dist2 as f32 <= (r as f32) * (r as f32) { fog[ny as usize][nx as usize] = true; }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raylib::prelude::Vector2;
+
+    const SEED: u64 = 1234;
+
+    fn world() -> GameWorld {
+        let mut w = GameWorld::new(SEED);
+        w.enemies.clear();
+        w
+    }
+
+    fn put_player(w: &mut GameWorld, cell: (usize, usize), angle: f32) {
+        let bs = w.block_size as f32;
+        w.player.pos = Vector2::new(cell.0 as f32 * bs + bs / 2.0, cell.1 as f32 * bs + bs / 2.0);
+        w.player.a = angle;
+    }
+
+    fn find_cell(w: &GameWorld, ch: char) -> (usize, usize) {
+        for (y, row) in w.maze.iter().enumerate() {
+            if let Some(x) = row.iter().position(|&c| c == ch) { return (x, y); }
+        }
+        panic!("no hay celda '{}'", ch);
+    }
+
+    #[test]
+    fn same_seed_builds_same_world() {
+        let a = GameWorld::new(SEED);
+        let b = GameWorld::new(SEED);
+        assert_eq!(a.maze, b.maze);
+        let cells_a: Vec<_> = a.pickups.iter().map(|p| (p.cell, p.item)).collect();
+        let cells_b: Vec<_> = b.pickups.iter().map(|p| (p.cell, p.item)).collect();
+        assert_eq!(cells_a, cells_b);
+    }
+
+    #[test]
+    fn same_inputs_give_same_result() {
+        let mut a = GameWorld::new(SEED);
+        let mut b = GameWorld::new(SEED);
+        let frames: Vec<InputFrame> = (0..240)
+            .map(|i| InputFrame { forward: 1.0, turn: if i % 60 < 20 { 1.5 } else { 0.0 }, sprint: i % 3 == 0, ..Default::default() })
+            .collect();
+        for f in frames.iter() {
+            let ea = a.step(f, 1.0 / 60.0);
+            let eb = b.step(f, 1.0 / 60.0);
+            assert_eq!(ea, eb);
+        }
+        assert_eq!(a.player.pos, b.player.pos);
+        assert_eq!(a.player.a, b.player.a);
+    }
+
+    #[test]
+    fn idle_step_emits_nothing() {
+        let mut w = world();
+        let events = w.step(&InputFrame::default(), 1.0 / 60.0);
+        assert!(events.is_empty(), "{:?}", events);
+    }
+
+    #[test]
+    fn using_a_pickup_adds_it_to_the_inventory() {
+        let mut w = world();
+        let idx = w.pickups.iter().position(|p| p.item == Item::Medkit).unwrap();
+        let cell = w.pickups[idx].cell;
+        put_player(&mut w, cell, 0.0);
+        let events = w.step(&InputFrame { use_target: true, ..Default::default() }, 1.0 / 60.0);
+        assert!(events.contains(&GameEvent::PickedUp(Item::Medkit)));
+        assert!(w.pickups[idx].taken);
+        assert_eq!(w.player.inventory.count(Item::Medkit), 1);
+    }
+
+    #[test]
+    fn enemy_contact_damages_the_player() {
+        let mut w = GameWorld::new(SEED);
+        w.enemies.truncate(1);
+        w.enemies[0].pos = w.player.pos;
+        let before = w.player.health;
+        let events = w.step(&InputFrame::default(), 1.0 / 60.0);
+        assert!(events.contains(&GameEvent::Hurt(50.0)));
+        assert!(w.player.health < before);
+    }
+
+    #[test]
+    fn goal_needs_the_gold_key() {
+        let mut w = world();
+        let goal = find_cell(&w, 'g');
+        put_player(&mut w, goal, 0.0);
+        let events = w.step(&InputFrame::default(), 1.0 / 60.0);
+        assert!(!events.contains(&GameEvent::Victory));
+        assert!(events.contains(&GameEvent::GoalLocked));
+
+        w.player.inventory.add(Item::Key(GOAL_LOCK));
+        let events = w.step(&InputFrame::default(), 1.0 / 60.0);
+        assert!(events.contains(&GameEvent::Victory));
+    }
+
+    #[test]
+    fn colored_door_opens_with_its_key() {
+        let mut w = world();
+        let door = w.doors.iter().find(|d| d.lock != GOAL_LOCK).unwrap();
+        let (cell, lock) = (door.cell, door.lock);
+        let (dx, dy) = [(1isize, 0isize), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .find(|&(dx, dy)| {
+                let (x, y) = (cell.0 as isize + dx, cell.1 as isize + dy);
+                w.maze[y as usize][x as usize] == ' '
+            })
+            .unwrap();
+        let stand = ((cell.0 as isize + dx) as usize, (cell.1 as isize + dy) as usize);
+        put_player(&mut w, stand, (-dy as f32).atan2(-dx as f32));
+
+        let events = w.step(&InputFrame { use_target: true, ..Default::default() }, 1.0 / 60.0);
+        assert!(events.contains(&GameEvent::DoorLocked(lock)));
+
+        w.player.inventory.add(Item::Key(lock));
+        let events = w.step(&InputFrame { use_target: true, ..Default::default() }, 1.0 / 60.0);
+        assert!(events.contains(&GameEvent::DoorOpened(lock)));
+        assert!(!w.player.inventory.has_key(lock));
+    }
+
+    fn flood(w: &GameWorld, open_locks: &[u8]) -> Vec<Vec<bool>> {
+        let start = ((w.player.pos.x as usize) / w.block_size, (w.player.pos.y as usize) / w.block_size);
+        let mut seen = vec![vec![false; w.maze[0].len()]; w.maze.len()];
+        let mut stack = vec![start];
+        seen[start.1][start.0] = true;
+        while let Some((x, y)) = stack.pop() {
+            for (nx, ny) in [(x + 1, y), (x.wrapping_sub(1), y), (x, y + 1), (x, y.wrapping_sub(1))] {
+                let Some(&ch) = w.maze.get(ny).and_then(|row| row.get(nx)) else { continue; };
+                if seen[ny][nx] { continue; }
+                let walkable = match ch {
+                    ' ' | 'p' | 'g' => true,
+                    'D' => door::lock_at(&w.doors, (nx, ny)).is_some_and(|l| open_locks.contains(&l)),
+                    _ => false,
+                };
+                if walkable {
+                    seen[ny][nx] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn keys_are_reachable_in_order_and_the_goal_is_solvable() {
+        for seed in [1, 7, 42, 1234, 9001] {
+            let w = GameWorld::new(seed);
+            let colored = w.doors.iter().map(|d| d.lock).filter(|&l| l != GOAL_LOCK).max().unwrap_or(0);
+            let key_cell = |id: u8| w.pickups.iter().find(|p| p.item == Item::Key(id)).map(|p| p.cell).unwrap();
+            for id in 1..=colored {
+                let region = flood(&w, &(1..id).collect::<Vec<u8>>());
+                let (x, y) = key_cell(id);
+                assert!(region[y][x], "semilla {}: la llave {} necesita llaves posteriores", seed, id);
+            }
+            let all_colored: Vec<u8> = (1..=colored).collect();
+            let (x, y) = key_cell(GOAL_LOCK);
+            assert!(flood(&w, &all_colored)[y][x], "semilla {}: la llave dorada es inalcanzable", seed);
+            let mut all = all_colored.clone();
+            all.push(GOAL_LOCK);
+            let (gx, gy) = find_cell(&w, 'g');
+            assert!(flood(&w, &all)[gy][gx], "semilla {}: la meta es inalcanzable", seed);
+        }
+    }
+
+    #[test]
+    fn crouching_lowers_the_eye_and_blocks_sprinting() {
+        let mut w = world();
+        let frame = InputFrame { forward: 1.0, sprint: true, crouch: true, tilt: 1.0, ..Default::default() };
+        for _ in 0..120 { w.step(&frame, 1.0 / 60.0); }
+        assert_eq!(w.player.pitch, crate::player::MAX_PITCH);
+        assert!(w.player.eye_height() < 0.35);
+        assert_eq!(w.player.stamina, w.player.stamina_max);
+    }
+}